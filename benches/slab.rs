@@ -0,0 +1,59 @@
+/*
+    COMPARES THE Rc<RefCell> LFUCache WITH THE SLAB-BACKED SlabLFUCache ON THE
+    SAME SKEWED TRACE. RUN WITH `cargo bench --bench slab`
+*/
+#[path = "../src/rng.rs"]
+#[allow(dead_code)]
+mod rng;
+
+use lfu::cache::Cache;
+use lfu::cache::LFU::LFUCache;
+use lfu::cache::SLAB::SlabLFUCache;
+use rng::XorShift;
+use std::time::{Duration, Instant};
+
+const OPERATIONS: usize = 2_000_000;
+
+/*   HALF OF THE ACCESSES GO TO A SMALL HOT SET, THE REST ARE SPREAD OVER THE KEY SPACE   */
+fn trace(keys: i32, len: usize) -> Vec<i32> {
+    let mut rng = XorShift::new(0);
+    let mut trace = Vec::with_capacity(len);
+    for _ in 0..len {
+        let key = if rng.below(2) == 0 {
+            rng.below((keys / 16).max(1) as u64) as i32
+        } else {
+            rng.below(keys as u64) as i32
+        };
+        trace.push(key);
+    }
+    trace
+}
+
+fn run<C: Cache<i32, i32>>(cache: &mut C, trace: &[i32]) -> (Duration, usize) {
+    let start = Instant::now();
+    let mut hits = 0;
+    for &key in trace {
+        match cache.get(&key) {
+            Some(_) => hits += 1,
+            None => cache.put(key, key),
+        }
+    }
+    (start.elapsed(), hits)
+}
+
+fn main() {
+    for &capacity in &[100, 1_000, 10_000, 100_000] {
+        let trace = trace(capacity * 4, OPERATIONS);
+        let (rc, rc_hits) = run(&mut LFUCache::new(capacity), &trace);
+        let (slab, slab_hits) = run(&mut SlabLFUCache::new(capacity), &trace);
+        assert_eq!(rc_hits, slab_hits);
+        let rate = |d: Duration| OPERATIONS as f64 / d.as_secs_f64() / 1e6;
+        println!(
+            "capacity {:>7}: Rc<RefCell> {:>6.2} Mops/s, slab {:>6.2} Mops/s, speedup {:.2}x",
+            capacity,
+            rate(rc),
+            rate(slab),
+            rc.as_secs_f64() / slab.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,198 @@
+/*
+    LOAD BENCHMARK FOR `LFUCache`. EVERY WORKLOAD IS A KEY STREAM GENERATED UP
+    FRONT FROM A XORSHIFT PRNG, AND EVERY ACCESS IS A `get` FOLLOWED BY A `put`
+    ON A MISS. EACH CAPACITY RUNS TWICE ON A FRESH CACHE: ONCE UNTIMED PER
+    ACCESS FOR OPS/SEC AND HIT RATIO, ONCE TIMING EVERY ACCESS FOR LATENCY
+    PERCENTILES, SO THE CLOCK READS DO NOT SKEW THE THROUGHPUT.
+
+    cargo run --release --bin lfu-bench -- [--ops N] [--keys N] [--alpha A] [--capacities A,B,..]
+*/
+#[path = "../rng.rs"]
+#[allow(dead_code)]
+mod rng;
+
+use lfu::cache::LFU::LFUCache;
+use rng::XorShift;
+use std::env;
+use std::process;
+use std::str::FromStr;
+use std::time::Instant;
+
+struct Config {
+    ops: usize,
+    keys: usize,
+    alpha: f64,
+    capacities: Vec<i32>,
+}
+
+/*   KEY k IS DRAWN WITH PROBABILITY PROPORTIONAL TO 1 / (k + 1)^alpha   */
+fn zipf(rng: &mut XorShift, config: &Config) -> Vec<i32> {
+    let mut cdf = Vec::with_capacity(config.keys);
+    let mut total = 0.0;
+    for k in 0..config.keys {
+        total += 1.0 / ((k + 1) as f64).powf(config.alpha);
+        cdf.push(total);
+    }
+    (0..config.ops)
+        .map(|_| {
+            let u = rng.unit() * total;
+            cdf.partition_point(|&c| c < u).min(config.keys - 1) as i32
+        })
+        .collect()
+}
+
+fn uniform(rng: &mut XorShift, config: &Config) -> Vec<i32> {
+    (0..config.ops)
+        .map(|_| rng.below(config.keys as u64) as i32)
+        .collect()
+}
+
+/*   0, 1, .., keys - 1, 0, 1, ..: THE WORST CASE FOR ANY CACHE SMALLER THAN THE LOOP   */
+fn scan(_: &mut XorShift, config: &Config) -> Vec<i32> {
+    (0..config.ops).map(|i| (i % config.keys) as i32).collect()
+}
+
+/*
+    90% OF ACCESSES GO TO A HOT RANGE OF 1% OF THE KEYS, WHICH JUMPS TO A NEW
+    RANGE TEN TIMES OVER THE RUN. FREQUENCIES FROM THE OLD HOTSPOT GO STALE
+*/
+fn hotspot(rng: &mut XorShift, config: &Config) -> Vec<i32> {
+    let hot = (config.keys / 100).max(1);
+    let period = (config.ops / 10).max(1);
+    (0..config.ops)
+        .map(|i| {
+            if rng.unit() < 0.9 {
+                let base = (i / period * hot) % config.keys;
+                ((base + rng.below(hot as u64) as usize) % config.keys) as i32
+            } else {
+                rng.below(config.keys as u64) as i32
+            }
+        })
+        .collect()
+}
+
+fn throughput(capacity: i32, trace: &[i32]) -> (f64, f64) {
+    let mut cache = LFUCache::new(capacity);
+    let mut hits = 0usize;
+    let start = Instant::now();
+    for &key in trace {
+        if cache.get(key) == -1 {
+            cache.put(key, key);
+        } else {
+            hits += 1;
+        }
+    }
+    let seconds = start.elapsed().as_secs_f64();
+    (
+        trace.len() as f64 / seconds,
+        hits as f64 / trace.len() as f64,
+    )
+}
+
+fn latencies(capacity: i32, trace: &[i32]) -> Vec<u64> {
+    let mut cache = LFUCache::new(capacity);
+    let mut nanos = Vec::with_capacity(trace.len());
+    for &key in trace {
+        let start = Instant::now();
+        if cache.get(key) == -1 {
+            cache.put(key, key);
+        }
+        nanos.push(start.elapsed().as_nanos() as u64);
+    }
+    nanos.sort_unstable();
+    nanos
+}
+
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    let rank = ((sorted.len() as f64 * p).ceil() as usize).clamp(1, sorted.len());
+    sorted[rank - 1]
+}
+
+fn usage(message: &str) -> ! {
+    eprintln!("{}", message);
+    eprintln!("usage: lfu-bench [--ops N] [--keys N] [--alpha A] [--capacities A,B,..]");
+    process::exit(2)
+}
+
+fn parse<T: FromStr>(flag: &str, value: &str) -> T {
+    value
+        .parse()
+        .unwrap_or_else(|_| usage(&format!("bad value for {}: {}", flag, value)))
+}
+
+fn parse_args() -> Config {
+    let mut config = Config {
+        ops: 1_000_000,
+        keys: 100_000,
+        alpha: 0.99,
+        capacities: vec![100, 1_000, 10_000, 50_000],
+    };
+    let mut args = env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| usage(&format!("missing value for {}", flag)));
+        match flag.as_str() {
+            "--ops" => config.ops = parse(&flag, &value),
+            "--keys" => config.keys = parse(&flag, &value),
+            "--alpha" => config.alpha = parse(&flag, &value),
+            "--capacities" => {
+                config.capacities = value.split(',').map(|c| parse(&flag, c.trim())).collect()
+            }
+            _ => usage(&format!("unknown flag {}", flag)),
+        }
+    }
+    if config.ops == 0 || config.keys == 0 || config.keys > i32::MAX as usize {
+        usage("--ops and --keys must be positive, and --keys must fit in i32");
+    }
+    config
+}
+
+fn main() {
+    let config = parse_args();
+    if cfg!(debug_assertions) {
+        eprintln!(
+            "warning: debug builds check LFUCache invariants after every operation, use --release"
+        );
+    }
+    type Workload = fn(&mut XorShift, &Config) -> Vec<i32>;
+    let zipf_name = format!("zipf({})", config.alpha);
+    let workloads: [(&str, Workload); 4] = [
+        (&zipf_name, zipf),
+        ("uniform", uniform),
+        ("scan", scan),
+        ("hotspot", hotspot),
+    ];
+    println!("{} accesses over {} keys per run", config.ops, config.keys);
+    println!(
+        "{:<12} {:>9} {:>12} {:>8} {:>8} {:>8} {:>8} {:>9} {:>9}",
+        "workload",
+        "capacity",
+        "ops/sec",
+        "hit %",
+        "p50 ns",
+        "p90 ns",
+        "p99 ns",
+        "p99.9 ns",
+        "max ns"
+    );
+    for (name, generate) in workloads.iter() {
+        let trace = generate(&mut XorShift::new(0), &config);
+        for &capacity in &config.capacities {
+            let (ops, hit_ratio) = throughput(capacity, &trace);
+            let nanos = latencies(capacity, &trace);
+            println!(
+                "{:<12} {:>9} {:>12.0} {:>8.2} {:>8} {:>8} {:>8} {:>9} {:>9}",
+                name,
+                capacity,
+                ops,
+                hit_ratio * 100.0,
+                percentile(&nanos, 0.5),
+                percentile(&nanos, 0.9),
+                percentile(&nanos, 0.99),
+                percentile(&nanos, 0.999),
+                nanos[nanos.len() - 1]
+            );
+        }
+    }
+}
@@ -1,8 +1,64 @@
+use core::fmt;
+
+/// The operations every replacement policy in this module supports, so that
+/// callers can pick a policy at runtime and use it through `dyn Cache`.
+pub trait Cache<K, V>: fmt::Display {
+    fn get(&mut self, key: &K) -> Option<V>;
+    fn put(&mut self, key: K, value: V);
+    fn remove(&mut self, key: &K) -> Option<V>;
+    fn len(&self) -> usize;
+    fn capacity(&self) -> usize;
+    fn clear(&mut self);
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/*   `show` OUTPUT FOR THE POLICIES THAT KEEP ALL THEIR KEYS IN ONE LIST   */
+fn display_list(f: &mut fmt::Formatter<'_>, len: usize, cap: i32, list: &LFU::Freq) -> fmt::Result {
+    writeln!(f, "length: {}", len)?;
+    writeln!(f, "capacity: {}", cap)?;
+    if list.head.is_none() {
+        return write!(f, "[empty]");
+    }
+    let mut node = list.head.clone();
+    while let Some(n) = node {
+        write!(f, "[ key: {}, value: {} ]", n.borrow().key, n.borrow().val)?;
+        node = n.borrow().next.clone();
+        if node.is_some() {
+            write!(f, " -> ")?;
+        }
+    }
+    Ok(())
+}
+
+/*
+    THE SHARED CACHES DO NOT TRY TO RECOVER A LOCK POISONED BY A PANIC: THE SLAB
+    BEHIND IT MAY HAVE BEEN LEFT HALFWAY THROUGH AN UPDATE, SO EVERY
+    LATER CALLER PANICS TOO INSTEAD OF READING A CACHE THAT MAY BE BROKEN
+*/
+#[cfg(feature = "std")]
+fn unpoisoned<G>(result: std::sync::LockResult<G>) -> G {
+    result.expect("a thread panicked while holding a cache lock")
+}
+
+#[allow(non_snake_case)]
 pub mod LFU {
-    use std::cell::RefCell;
-    use std::collections::HashMap;
-    use std::fmt;
-    use std::rc::{Rc, Weak};
+    use super::Cache;
+    #[cfg(feature = "std")]
+    use crate::rng::XorShift;
+    use crate::table::HashMap;
+    #[cfg(feature = "std")]
+    use alloc::boxed::Box;
+    use alloc::collections::VecDeque;
+    use alloc::rc::{Rc, Weak};
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+    use core::fmt;
+    #[cfg(feature = "std")]
+    use std::time::{Duration, Instant};
 
     #[derive(Debug)]
     pub struct LFUCache {
@@ -11,28 +67,161 @@ pub mod LFU {
         keys: HashMap<i32, Rc<RefCell<Node>>>,
         freqs: HashMap<i32, Rc<RefCell<Freq>>>,
         head: Option<Rc<RefCell<Freq>>>,
+        ghost: Ghost,
+        eviction: Eviction,
+        #[cfg(feature = "std")]
+        absent: Option<Box<Negative>>,
+        read_increment: i32,
+        write_increment: i32,
+        sliding: Option<Sliding>,
+        #[cfg(feature = "std")]
+        log: Option<LogCounter>,
+        #[cfg(feature = "std")]
+        clock: fn() -> Instant,
+        spare_nodes: Vec<Rc<RefCell<Node>>>,
+        spare_freqs: Vec<Rc<RefCell<Freq>>>,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Lookup {
+        Hit(i32),
+        /// The key is known not to exist, see `put_absent`.
+        Absent,
+        Miss,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Eviction {
+        /// One key is evicted per insert once the cache is full.
+        Single,
+        /// An insert that would take the length past `high` first evicts
+        /// down to `low` in one batch.
+        Batch { high: i32, low: i32 },
+        /// Nothing is evicted until the cache is full, which then evicts down
+        /// to `low` in one batch. `maintain` does the same whenever the
+        /// length is past `high`, so it can run off the insert path.
+        Deferred { high: i32, low: i32 },
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Counter {
+        /// Frequency is the exact number of accesses.
+        Exact,
+        /// Frequency is a Redis style 8 bit logarithmic counter. New keys
+        /// start at 5, every access increments it with probability
+        /// 1 / ((counter - 5) * factor + 1), and it loses 1 for every
+        /// `decay` the key goes without being accessed. The loss is applied
+        /// when the key is accessed and by a sweep that decays a few keys on
+        /// every eviction, so keys that stopped being read drop as well.
+        /// Needs `std` for the clock.
+        #[cfg(feature = "std")]
+        Logarithmic { factor: i32, decay: Duration },
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Window {
+        /// The last given number of gets and puts.
+        Operations(u64),
+        /// The last given stretch of time. Needs `std` for the clock.
+        #[cfg(feature = "std")]
+        Time(Duration),
+    }
+
+    /// What `check_invariants` found wrong, naming the bucket by its
+    /// frequency and the node by its key.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum InvariantViolation {
+        /// `keys.len()` differs from `len`.
+        Length { keys: usize, len: i32 },
+        /// `head` is missing or has a previous bucket.
+        Head,
+        /// A bucket does not have a higher frequency than the one before it.
+        Order { prev: i32, freq: i32 },
+        /// `freqs` does not map this frequency to the bucket in the chain.
+        FreqIndex { freq: i32 },
+        /// A bucket other than the lone head of an empty cache has no nodes.
+        EmptyBucket { freq: i32 },
+        /// The bucket's `head` or `tail` is not the end of its node list.
+        Ends { freq: i32 },
+        /// A `prev` link in or around this bucket does not mirror `next`.
+        Links { freq: i32 },
+        /// The node's `parent` is not the bucket that holds it.
+        Parent { key: i32, freq: i32 },
+        /// The node's `freq` is not the frequency of the bucket that holds it.
+        Frequency { key: i32, freq: i32, bucket: i32 },
+        /// `keys` does not map this key to the node in the chain.
+        KeyIndex { key: i32 },
+    }
+
+    impl fmt::Display for InvariantViolation {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match *self {
+                InvariantViolation::Length { keys, len } => {
+                    write!(f, "{} keys indexed but length is {}", keys, len)
+                }
+                InvariantViolation::Head => write!(f, "head bucket is missing or not first"),
+                InvariantViolation::Order { prev, freq } => {
+                    write!(f, "bucket {} follows bucket {}", freq, prev)
+                }
+                InvariantViolation::FreqIndex { freq } => {
+                    write!(f, "freqs disagrees with the chain at {}", freq)
+                }
+                InvariantViolation::EmptyBucket { freq } => write!(f, "bucket {} is empty", freq),
+                InvariantViolation::Ends { freq } => {
+                    write!(f, "bucket {} head or tail is wrong", freq)
+                }
+                InvariantViolation::Links { freq } => {
+                    write!(f, "asymmetric prev/next links at bucket {}", freq)
+                }
+                InvariantViolation::Parent { key, freq } => {
+                    write!(f, "key {} in bucket {} has another parent", key, freq)
+                }
+                InvariantViolation::Frequency { key, freq, bucket } => write!(
+                    f,
+                    "key {} with frequency {} is in bucket {}",
+                    key, freq, bucket
+                ),
+                InvariantViolation::KeyIndex { key } => {
+                    write!(f, "keys disagrees with the chain at key {}", key)
+                }
+            }
+        }
     }
     #[derive(Debug)]
-    struct Node {
-        key: i32,
-        val: i32,
+    pub(super) struct Node {
+        pub(super) key: i32,
+        pub(super) val: i32,
         freq: i32,
-        next: Option<Rc<RefCell<Node>>>,
+        pub(super) next: Option<Rc<RefCell<Node>>>,
         prev: Option<Weak<RefCell<Node>>>,
         parent: Option<Weak<RefCell<Freq>>>,
+        counts: Vec<i32>,
+        #[cfg(feature = "std")]
+        period: u64,
     }
 
     impl Node {
-        fn new(key: i32, value: i32) -> Self {
+        pub(super) fn new(key: i32, value: i32) -> Self {
             Node {
-                key: key,
+                key,
                 val: value,
                 freq: 1,
                 next: None,
                 prev: None,
                 parent: None,
+                counts: Vec::new(),
+                #[cfg(feature = "std")]
+                period: 0,
             }
         }
+
+        /*   THE FREQUENCY WITHIN THE WINDOW, NEVER BELOW 1 AND NEVER PAST i32::MAX   */
+        fn windowed(&self) -> i32 {
+            self.counts
+                .iter()
+                .fold(0i32, |sum, &count| sum.saturating_add(count))
+                .max(1)
+        }
     }
 
     impl PartialEq for Node {
@@ -43,47 +232,54 @@ pub mod LFU {
 
     impl fmt::Display for LFUCache {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            write!(f, "length: {}\n", self.len);
-            write!(f, "capacity: {}\n", self.cap);
+            writeln!(f, "length: {}", self.len)?;
+            writeln!(f, "capacity: {}", self.cap)?;
+            #[cfg(feature = "std")]
+            {
+                if let Some(absent) = self.absent.as_ref() {
+                    writeln!(f, "absent markers: {}", absent.markers.len)?;
+                }
+            }
             if self.len == 0 {
                 return write!(f, "[empty]");
             }
-            fn print_child(f: &mut fmt::Formatter<'_>, ch: Rc<RefCell<Node>>) {
+            fn print_child(f: &mut fmt::Formatter<'_>, ch: Rc<RefCell<Node>>) -> fmt::Result {
                 write!(
                     f,
                     "[ key: {}, value: {} ]",
                     ch.borrow().key,
                     ch.borrow().val
-                );
+                )?;
                 if ch.borrow().next.is_some() {
-                    print!(" -> ");
-                    print_child(f, ch.borrow().next.as_ref().unwrap().clone())
+                    write!(f, " -> ")?;
+                    print_child(f, ch.borrow().next.as_ref().unwrap().clone())?;
                 }
+                Ok(())
             }
-            fn dis(f: &mut fmt::Formatter<'_>, freq: Rc<RefCell<Freq>>) {
-                print!("frequency {} : ", freq.borrow().f);
-                print_child(f, freq.borrow().head.as_ref().unwrap().clone());
+            fn dis(f: &mut fmt::Formatter<'_>, freq: Rc<RefCell<Freq>>) -> fmt::Result {
+                write!(f, "frequency {} : ", freq.borrow().f)?;
+                print_child(f, freq.borrow().head.as_ref().unwrap().clone())?;
                 if freq.borrow().next.is_some() {
-                    print!("\n");
-                    dis(f, freq.borrow().next.as_ref().unwrap().clone())
+                    writeln!(f)?;
+                    dis(f, freq.borrow().next.as_ref().unwrap().clone())?;
                 }
+                Ok(())
             }
-            dis(f, self.head.as_ref().unwrap().clone());
-            write!(f, "")
+            dis(f, self.head.as_ref().unwrap().clone())
         }
     }
 
     #[derive(Debug)]
-    struct Freq {
+    pub(super) struct Freq {
         f: i32,
-        head: Option<Rc<RefCell<Node>>>,
+        pub(super) head: Option<Rc<RefCell<Node>>>,
         tail: Option<Rc<RefCell<Node>>>,
         next: Option<Rc<RefCell<Freq>>>,
         prev: Option<Weak<RefCell<Freq>>>,
     }
 
     impl Freq {
-        fn new(frequency: i32) -> Self {
+        pub(super) fn new(frequency: i32) -> Self {
             Freq {
                 f: frequency,
                 head: None,
@@ -92,10 +288,267 @@ pub mod LFU {
                 prev: None,
             }
         }
+
+        /*
+            PLAIN LIST OPERATIONS ON THE NODES OF ONE BUCKET.
+            THEY DO NOT TOUCH `parent`, THE CALLER OWNS THAT LINK
+        */
+        pub(super) fn push_back(&mut self, node: Rc<RefCell<Node>>) {
+            match self.tail.take() {
+                Some(tail) => {
+                    node.borrow_mut().prev = Some(Rc::downgrade(&tail));
+                    tail.borrow_mut().next = Some(node.clone());
+                }
+                None => self.head = Some(node.clone()),
+            }
+            self.tail = Some(node);
+        }
+
+        pub(super) fn unlink(&mut self, node: &Rc<RefCell<Node>>) {
+            let prev = node.borrow_mut().prev.take().and_then(|prev| prev.upgrade());
+            let next = node.borrow_mut().next.take();
+            match prev.as_ref() {
+                Some(prev) => prev.borrow_mut().next = next.clone(),
+                None => self.head = next.clone(),
+            }
+            match next {
+                Some(next) => next.borrow_mut().prev = prev.as_ref().map(Rc::downgrade),
+                None => self.tail = prev,
+            }
+        }
+
+        pub(super) fn pop_front(&mut self) -> Option<Rc<RefCell<Node>>> {
+            let head = self.head.clone()?;
+            self.unlink(&head);
+            Some(head)
+        }
+    }
+
+    /*
+        KEYS IN THE ORDER THEY WERE LAST RECORDED, EACH WITH A VALUE. EVERY
+        RECORD GETS A SEQUENCE NUMBER, SO THAT THE ENTRY A RE-RECORDED OR
+        REMOVED KEY LEAVES IN `order` IS SKIPPED INSTEAD OF SEARCHED FOR
+    */
+    #[derive(Debug)]
+    struct History<T> {
+        seq: u64,
+        order: VecDeque<(i32, u64)>,
+        entries: HashMap<i32, (T, u64)>,
+    }
+
+    impl<T> History<T> {
+        fn new() -> Self {
+            History {
+                seq: 0,
+                order: VecDeque::new(),
+                entries: HashMap::new(),
+            }
+        }
+
+        fn len(&self) -> usize {
+            self.entries.len()
+        }
+
+        #[cfg(feature = "std")]
+        fn get(&self, key: i32) -> Option<&T> {
+            self.entries.get(&key).map(|(val, _)| val)
+        }
+
+        fn record(&mut self, key: i32, val: T) {
+            self.seq += 1;
+            self.entries.insert(key, (val, self.seq));
+            self.order.push_back((key, self.seq));
+
+            /*   DROP STALE ENTRIES ONCE THEY OUTNUMBER THE LIVE ONES   */
+            if self.order.len() > 2 * self.entries.len().max(1) {
+                let entries = &self.entries;
+                self.order
+                    .retain(|(key, seq)| entries.get(key).map(|e| e.1) == Some(*seq));
+            }
+        }
+
+        fn remove(&mut self, key: i32) -> Option<T> {
+            self.entries.remove(&key).map(|(val, _)| val)
+        }
+
+        /*   THE LEAST RECENTLY RECORDED KEY, POPPING THE STALE ENTRIES IN FRONT OF IT   */
+        fn oldest(&mut self) -> Option<(i32, &T)> {
+            while let Some(&(key, seq)) = self.order.front() {
+                if self.entries.get(&key).map(|e| e.1) == Some(seq) {
+                    return self.entries.get(&key).map(|(val, _)| (key, val));
+                }
+                self.order.pop_front();
+            }
+            None
+        }
+
+        fn pop_oldest(&mut self) -> Option<(i32, T)> {
+            let key = self.oldest()?.0;
+            self.order.pop_front();
+            self.remove(key).map(|val| (key, val))
+        }
+
+        fn clear(&mut self) {
+            self.order.clear();
+            self.entries.clear();
+        }
+    }
+
+    /*   BOUNDED HISTORY OF EVICTED KEYS AND THE FREQUENCY THEY HAD   */
+    #[derive(Debug)]
+    struct Ghost {
+        cap: usize,
+        freqs: History<i32>,
+    }
+
+    impl Ghost {
+        fn new(capacity: i32) -> Self {
+            Ghost {
+                cap: capacity.max(0) as usize,
+                freqs: History::new(),
+            }
+        }
+
+        fn remember(&mut self, key: i32, freq: i32) {
+            if self.cap == 0 {
+                return;
+            }
+            self.freqs.record(key, freq);
+            while self.freqs.len() > self.cap {
+                self.freqs.pop_oldest();
+            }
+        }
+
+        fn forget(&mut self, key: i32) -> Option<i32> {
+            self.freqs.remove(key)
+        }
+
+        fn clear(&mut self) {
+            self.freqs.clear();
+        }
+    }
+
+    /*
+        "KNOWN ABSENT" MARKERS. THEY LIVE IN A CACHE OF THEIR OWN SO THAT THEY
+        HAVE A SEPARATE BUDGET BUT ARE STILL EVICTED BY FREQUENCY, AND EVERY
+        MARKER EXPIRES `ttl` AFTER IT WAS PUT. ALL MARKERS SHARE THE TTL, SO
+        THE ORDER OF `expires` IS ALSO EXPIRY ORDER
+    */
+    #[cfg(feature = "std")]
+    #[derive(Debug)]
+    struct Negative {
+        ttl: Duration,
+        expires: History<Instant>,
+        markers: LFUCache,
+    }
+
+    #[cfg(feature = "std")]
+    impl Negative {
+        /*   EXPIRED MARKERS GO FIRST, SO THEY NEVER TAKE BUDGET FROM LIVE ONES   */
+        fn purge(&mut self, now: Instant) {
+            while let Some((key, &expires)) = self.expires.oldest() {
+                if expires > now {
+                    break;
+                }
+                self.expires.pop_oldest();
+                self.markers.take(key);
+            }
+        }
+
+        fn insert(&mut self, key: i32, now: Instant) {
+            self.expires.record(key, now + self.ttl);
+        }
+    }
+
+    /*
+        SLIDING WINDOW COUNTING. THE WINDOW IS SPLIT INTO `slots` EPOCHS AND
+        EVERY NODE COUNTS ITS ACCESSES PER EPOCH IN `Node.counts`, INDEXED BY
+        EPOCH MODULO `slots`. `touched` LISTS THE KEYS WITH A NON ZERO COUNT IN
+        EACH SLOT, SO THAT WHEN A SLOT EXPIRES ONLY THOSE NODES ARE VISITED
+    */
+    #[derive(Debug)]
+    struct Sliding {
+        window: Window,
+        slots: usize,
+        epoch: u64,
+        ops: u64,
+        #[cfg(feature = "std")]
+        start: Instant,
+        #[cfg(feature = "std")]
+        clock: fn() -> Instant,
+        touched: Vec<Vec<i32>>,
+    }
+
+    impl Sliding {
+        fn current_epoch(&self) -> u64 {
+            match self.window {
+                Window::Operations(n) => self.ops / (n / self.slots as u64).max(1),
+                #[cfg(feature = "std")]
+                Window::Time(span) => {
+                    let width = (span / self.slots as u32).as_nanos().max(1);
+                    let elapsed = (self.clock)().saturating_duration_since(self.start);
+                    (elapsed.as_nanos() / width) as u64
+                }
+            }
+        }
+
+        fn slot(&self) -> usize {
+            (self.epoch % self.slots as u64) as usize
+        }
+    }
+
+    #[cfg(feature = "std")]
+    const LOG_INIT: i32 = 5;
+    #[cfg(feature = "std")]
+    const LOG_MAX: i32 = 255;
+    #[cfg(feature = "std")]
+    const LOG_SAMPLES: usize = 5;
+
+    /*
+        STATE OF THE LOGARITHMIC COUNTER MODE. TIME IS COUNTED IN DECAY
+        PERIODS SINCE `start`, EVERY NODE REMEMBERS THE PERIOD IT WAS LAST
+        DECAYED IN. `rng` DRIVES THE PROBABILISTIC INCREMENT,
+        `sweep` THE KEY THE EVICTION SWEEP DECAYS NEXT
+    */
+    #[cfg(feature = "std")]
+    #[derive(Debug)]
+    struct LogCounter {
+        factor: i32,
+        decay: Duration,
+        start: Instant,
+        clock: fn() -> Instant,
+        rng: XorShift,
+        sweep: Option<i32>,
+    }
+
+    #[cfg(feature = "std")]
+    impl LogCounter {
+        fn period(&self) -> u64 {
+            let elapsed = (self.clock)().saturating_duration_since(self.start);
+            (elapsed.as_nanos() / self.decay.as_nanos().max(1)) as u64
+        }
+
+        fn increments(&mut self, counter: i32) -> bool {
+            if counter >= LOG_MAX {
+                return false;
+            }
+            let r = self.rng.unit();
+            let base = (counter - LOG_INIT).max(0);
+            r < 1.0 / (base as f64 * self.factor as f64 + 1.0)
+        }
     }
 
     impl LFUCache {
         pub fn new(capacity: i32) -> Self {
+            LFUCache::with_ghost(capacity, 0)
+        }
+
+        /// Like `new`, but remembers the frequencies of up to `ghost_capacity`
+        /// evicted keys so that a key put back soon after eviction resumes
+        /// from its old frequency instead of starting over at 1. If no other
+        /// key has that frequency, finding the place of its bucket walks the
+        /// bucket chain, so such a put costs O(number of distinct frequencies).
+        pub fn with_ghost(capacity: i32, ghost_capacity: i32) -> Self {
             let freq = Rc::new(RefCell::new(Freq::new(1)));
             let mut f: HashMap<i32, Rc<RefCell<Freq>>> = HashMap::new();
             f.insert(1, freq.clone());
@@ -105,6 +558,19 @@ pub mod LFU {
                 freqs: f,
                 cap: capacity,
                 len: 0,
+                ghost: Ghost::new(ghost_capacity),
+                eviction: Eviction::Single,
+                #[cfg(feature = "std")]
+                absent: None,
+                read_increment: 1,
+                write_increment: 1,
+                sliding: None,
+                #[cfg(feature = "std")]
+                log: None,
+                #[cfg(feature = "std")]
+                clock: Instant::now,
+                spare_nodes: Vec::new(),
+                spare_freqs: Vec::new(),
             }
         }
 
@@ -132,7 +598,7 @@ pub mod LFU {
                 /// */
 
                 /* CREATE NEW PARENT */
-                let next_parent = Rc::new(RefCell::new(Freq::new(node.borrow().freq)));
+                let next_parent = self.new_freq(node.borrow().freq);
                 self.freqs.insert(node.borrow().freq, next_parent.clone());
 
                 /* SET THE REFERENCE TO A NEW PARENT NODE FROM PREVIOUS PARENT */
@@ -206,7 +672,7 @@ pub mod LFU {
                 /// */
 
                 /*  WE CREATE NEW PARENT NODE TO INSERT BETWEEN NODE PARENT AND NEXT PARENT */
-                let new_parent = Rc::new(RefCell::new(Freq::new(node.borrow().freq)));
+                let new_parent = self.new_freq(node.borrow().freq);
                 self.freqs.insert(node.borrow().freq, new_parent.clone());
 
                 /* MAKE NEXT FREQUENCY NODE TO POINT TO OUR NEW PARENT NODE */
@@ -412,8 +878,18 @@ pub mod LFU {
                             .unwrap()
                             .clone(),
                     );
+                } else {
+                    node.borrow()
+                        .parent
+                        .as_ref()
+                        .unwrap()
+                        .upgrade()
+                        .unwrap()
+                        .borrow_mut()
+                        .prev = None;
                 }
-                self.freqs.remove(&(node.borrow().freq - 1));
+                let old_parent = self.freqs.remove(&(node.borrow().freq - 1)).unwrap();
+                self.recycle_freq(old_parent);
             }
 
             /*
@@ -428,110 +904,1016 @@ pub mod LFU {
             }
         }
 
+        /// How much a read hit and a write to an existing key add to its
+        /// frequency. With 0 the value is still read or updated but the key
+        /// keeps its frequency and its place in the bucket.
+        pub fn set_increments(&mut self, read: i32, write: i32) {
+            self.read_increment = read.max(0);
+            self.write_increment = write.max(0);
+        }
+
+        /// Like `new`, with the choice of how frequency is counted.
+        /// With `Counter::Logarithmic` frequencies stay within 0..=255, so
+        /// there are never more than 256 buckets.
+        pub fn with_counter(capacity: i32, counter: Counter) -> Self {
+            match counter {
+                Counter::Exact => LFUCache::new(capacity),
+                #[cfg(feature = "std")]
+                Counter::Logarithmic { factor, decay } => LFUCache {
+                    log: Some(LogCounter {
+                        factor: factor.max(0),
+                        decay,
+                        start: Instant::now(),
+                        clock: Instant::now,
+                        rng: XorShift::new(0),
+                        sweep: None,
+                    }),
+                    ..LFUCache::new(capacity)
+                },
+            }
+        }
+
+        /*   LOWER A LOGARITHMIC COUNTER BY THE DECAY PERIODS SINCE IT WAS LAST DECAYED   */
+        #[cfg(feature = "std")]
+        fn decay(&mut self, node: &Rc<RefCell<Node>>) {
+            let now = match self.log.as_ref() {
+                Some(log) => log.period(),
+                None => return,
+            };
+            let (freq, periods) = {
+                let mut node = node.borrow_mut();
+                let periods = now.saturating_sub(node.period);
+                node.period = now;
+                (node.freq, periods.min(LOG_MAX as u64) as i32)
+            };
+            if periods > 0 && freq > 0 {
+                self.move_down(node.clone(), (freq - periods).max(0));
+            }
+        }
+
+        /*
+            REDIS DECAYS THE KEYS IT SAMPLES FOR EVICTION. HERE EVERY EVICTION DECAYS THE
+            NEXT `LOG_SAMPLES` KEYS OF A SWEEP ALONG THE BUCKET CHAIN, SO A KEY THAT WENT
+            COLD IS REACHED AND DROPS EVEN IF NOTHING EVER READS IT AGAIN
+        */
+        #[cfg(feature = "std")]
+        fn sweep(&mut self) {
+            let mut cursor = match self.log.as_mut() {
+                Some(log) => log.sweep.take(),
+                None => return,
+            };
+            for _ in 0..LOG_SAMPLES {
+                let node = match cursor.and_then(|key| self.keys.get(&key)) {
+                    Some(node) => node.clone(),
+                    None => match self.head.as_ref().unwrap().borrow().head.clone() {
+                        Some(node) => node,
+                        None => break,
+                    },
+                };
+                cursor = LFUCache::following(&node);
+                self.decay(&node);
+            }
+            self.log.as_mut().unwrap().sweep = cursor;
+        }
+
+        /*   KEY AFTER THIS ONE IN CHAIN ORDER: ITS BUCKET FIRST, THEN THE NEXT BUCKET   */
+        #[cfg(feature = "std")]
+        fn following(node: &Rc<RefCell<Node>>) -> Option<i32> {
+            let node = node.borrow();
+            if let Some(next) = node.next.as_ref() {
+                return Some(next.borrow().key);
+            }
+            let parent = node.parent.as_ref()?.upgrade()?;
+            let next = parent.borrow().next.clone()?;
+            let key = next.borrow().head.as_ref().map(|head| head.borrow().key);
+            key
+        }
+
+        /// Makes the frequency of a key count only its accesses within
+        /// `window`, tracked in `slots` sub-counters: the more slots, the
+        /// smoother old accesses fall out. Keys drop to lower frequencies as
+        /// that happens, but never below 1 while they are in the cache.
+        /// An `Operations(n)` window has at most `n` slots.
+        pub fn set_window(&mut self, window: Window, slots: usize) {
+            /*   SLOTS NARROWER THAN ONE OPERATION WOULD STRETCH THE WINDOW TO `slots` OPERATIONS   */
+            let slots = match window {
+                Window::Operations(n) => slots.min(n as usize),
+                #[cfg(feature = "std")]
+                Window::Time(_) => slots,
+            }
+            .max(1);
+            let mut touched = vec![Vec::new(); slots];
+
+            /*   ACCESSES COUNTED SO FAR ARE TREATED AS HAPPENING NOW   */
+            for (key, node) in self.keys.iter() {
+                let mut node = node.borrow_mut();
+                node.counts = vec![0; slots];
+                node.counts[0] = node.freq;
+                touched[0].push(*key);
+            }
+            self.sliding = Some(Sliding {
+                window,
+                slots,
+                epoch: 0,
+                ops: 0,
+                #[cfg(feature = "std")]
+                start: (self.clock)(),
+                #[cfg(feature = "std")]
+                clock: self.clock,
+                touched,
+            });
+        }
+
+        /*   ADVANCE THE WINDOW AND MOVE DOWN THE NODES WHOSE OLDEST ACCESSES FELL OUT OF IT   */
+        fn tick(&mut self) {
+            let (from, to, slots) = match self.sliding.as_mut() {
+                Some(sliding) => {
+                    sliding.ops += 1;
+                    let epoch = sliding.current_epoch();
+                    if epoch == sliding.epoch {
+                        return;
+                    }
+                    let from = sliding.epoch;
+                    sliding.epoch = epoch;
+                    (from, epoch, sliding.slots as u64)
+                }
+                None => return,
+            };
+
+            /*   EVERY EPOCH WE ENTER REUSES ONE SLOT, SO THAT SLOT EXPIRES FIRST   */
+            for epoch in (from + 1)..=to.min(from + slots) {
+                let slot = (epoch % slots) as usize;
+                let touched = core::mem::take(&mut self.sliding.as_mut().unwrap().touched[slot]);
+                for key in touched {
+                    let node = match self.keys.get(&key) {
+                        Some(node) => node.clone(),
+                        None => continue,
+                    };
+                    let freq = {
+                        let mut node = node.borrow_mut();
+                        node.counts[slot] = 0;
+                        node.windowed()
+                    };
+                    if freq < node.borrow().freq {
+                        self.move_down(node, freq);
+                    }
+                }
+            }
+        }
+
+        /*   START THE SUB-COUNTERS OF A NODE THAT JUST ENTERED THE CACHE   */
+        fn count_new(&mut self, node: &Rc<RefCell<Node>>) {
+            if let Some(sliding) = self.sliding.as_mut() {
+                let slot = sliding.slot();
+                let mut node = node.borrow_mut();
+                node.counts.clear();
+                node.counts.resize(sliding.slots, 0);
+                node.counts[slot] = node.freq;
+                sliding.touched[slot].push(node.key);
+            }
+        }
+
+        /*
+            A STEP OF ONE IS THE CLASSIC `move_node`, A BIGGER INCREMENT JUMPS STRAIGHT
+            TO ITS BUCKET. WITH A WINDOW THE INCREMENT IS COUNTED FIRST AND THE NODE
+            ONLY MOVES AS FAR AS ITS WINDOWED FREQUENCY REQUIRES
+        */
+        fn bump(&mut self, node: Rc<RefCell<Node>>, increment: i32) {
+            #[cfg(feature = "std")]
+            if self.log.is_some() {
+                self.decay(&node);
+                let log = self.log.as_mut().unwrap();
+                let freq = node.borrow().freq;
+                let mut target = freq;
+                for _ in 0..increment {
+                    if target >= LOG_MAX {
+                        break;
+                    }
+                    if log.increments(target) {
+                        target += 1;
+                    }
+                }
+                self.move_by(node, target - freq);
+                return;
+            }
+            let steps = match self.sliding.as_mut() {
+                Some(sliding) => {
+                    let slot = sliding.slot();
+                    let mut node = node.borrow_mut();
+                    if node.counts[slot] == 0 && increment > 0 {
+                        sliding.touched[slot].push(node.key);
+                    }
+                    node.counts[slot] = node.counts[slot].saturating_add(increment);
+                    node.windowed() - node.freq
+                }
+                None => increment,
+            };
+            self.move_by(node, steps);
+        }
+
+        /*   A FREQUENCY STOPS AT i32::MAX RATHER THAN WRAP AROUND TO THE FRONT OF THE CHAIN   */
+        fn move_by(&mut self, node: Rc<RefCell<Node>>, steps: i32) {
+            let freq = node.borrow().freq;
+            let target = freq.saturating_add(steps.max(0));
+            match target - freq {
+                0 => {}
+                1 => self.move_node(node),
+                _ => self.move_up(node, target),
+            }
+        }
+
+        /*   `move_node` BY MANY FREQUENCIES AT ONCE: WALK ON TO THE LAST BUCKET WITH A LOWER ONE   */
+        fn move_up(&mut self, node: Rc<RefCell<Node>>, freq: i32) {
+            let old = node.borrow().parent.as_ref().unwrap().upgrade().unwrap();
+            let target = match self.freqs.get(&freq) {
+                Some(target) => target.clone(),
+                None => {
+                    let target = self.new_freq(freq);
+                    self.freqs.insert(freq, target.clone());
+                    let mut prev = old.clone();
+                    loop {
+                        let next = prev.borrow().next.clone();
+                        match next {
+                            Some(next) if next.borrow().f < freq => prev = next,
+                            next => {
+                                if let Some(next) = next.as_ref() {
+                                    next.borrow_mut().prev = Some(Rc::downgrade(&target));
+                                }
+                                target.borrow_mut().next = next;
+                                target.borrow_mut().prev = Some(Rc::downgrade(&prev));
+                                prev.borrow_mut().next = Some(target.clone());
+                                break;
+                            }
+                        }
+                    }
+                    target
+                }
+            };
+            self.reparent(node, old, target, freq);
+        }
+
+        /*   THE OPPOSITE OF `move_node`: MOVE A NODE DOWN TO THE TAIL OF A LOWER BUCKET   */
+        fn move_down(&mut self, node: Rc<RefCell<Node>>, freq: i32) {
+            let old = node.borrow().parent.as_ref().unwrap().upgrade().unwrap();
+            let target = match self.freqs.get(&freq) {
+                Some(target) => target.clone(),
+                None => {
+                    let target = self.new_freq(freq);
+                    self.freqs.insert(freq, target.clone());
+
+                    /*   WALK BACK TO THE LAST BUCKET WITH A HIGHER FREQUENCY   */
+                    let mut next = old.clone();
+                    loop {
+                        let prev = next.borrow().prev.as_ref().and_then(|prev| prev.upgrade());
+                        match prev {
+                            Some(prev) if prev.borrow().f > freq => next = prev,
+                            prev => {
+                                match prev {
+                                    Some(prev) => {
+                                        prev.borrow_mut().next = Some(target.clone());
+                                        target.borrow_mut().prev = Some(Rc::downgrade(&prev));
+                                    }
+                                    None => self.head = Some(target.clone()),
+                                }
+                                next.borrow_mut().prev = Some(Rc::downgrade(&target));
+                                target.borrow_mut().next = Some(next);
+                                break;
+                            }
+                        }
+                    }
+                    target
+                }
+            };
+            self.reparent(node, old, target, freq);
+        }
+
+        /*   MOVE A NODE TO THE TAIL OF ANOTHER BUCKET, DROPPING THE OLD ONE IF THAT EMPTIED IT   */
+        fn reparent(
+            &mut self,
+            node: Rc<RefCell<Node>>,
+            old: Rc<RefCell<Freq>>,
+            target: Rc<RefCell<Freq>>,
+            freq: i32,
+        ) {
+            old.borrow_mut().unlink(&node);
+            target.borrow_mut().push_back(node.clone());
+            node.borrow_mut().freq = freq;
+            node.borrow_mut().parent = Some(Rc::downgrade(&target));
+            if old.borrow().head.is_none() {
+                self.remove_freq(old);
+            }
+        }
+
         pub fn get(&mut self, key: i32) -> i32 {
-            match self.keys.remove(&key) {
+            self.tick();
+            let val = match self.keys.get(&key).cloned() {
                 Some(node) => {
-                    self.move_node(node.clone());
-                    self.keys.insert(key, node.clone());
+                    self.bump(node.clone(), self.read_increment);
                     node.borrow().val
                 }
                 None => -1,
-            }
+            };
+            self.debug_check();
+            val
         }
 
         pub fn put(&mut self, key: i32, value: i32) {
             if self.cap == 0 {
                 return;
             }
-            match self.keys.remove(&key) {
+            self.tick();
+            match self.keys.get(&key).cloned() {
                 Some(node) => {
                     node.borrow_mut().val = value;
-                    self.move_node(node.clone());
-                    self.keys.insert(key, node.clone());
+                    self.bump(node, self.write_increment);
                 }
                 None => {
-                    let node = Rc::new(RefCell::new(Node::new(key, value)));
-                    if self.len == self.cap {
+                    #[cfg(feature = "std")]
+                    self.forget_absent(key);
+                    let low = match self.eviction {
+                        Eviction::Batch { high, low } if self.len >= high => Some(low),
+                        Eviction::Deferred { low, .. } if self.len == self.cap => Some(low),
+                        _ => None,
+                    };
+                    if let Some(low) = low {
+                        self.evict_batch(self.len - low);
+                        self.len += 1
+                    } else if self.len == self.cap {
                         self.invalidate()
                     } else {
                         self.len += 1
                     }
-                    self.add_new(node.clone());
+                    let node = self.new_node(key, value);
+                    #[cfg(feature = "std")]
+                    let initial = match self.log.as_ref() {
+                        Some(log) => {
+                            node.borrow_mut().period = log.period();
+                            LOG_INIT
+                        }
+                        None => 1,
+                    };
+                    #[cfg(not(feature = "std"))]
+                    let initial = 1;
+                    /*   A RETURNING KEY NEVER STARTS BELOW A BRAND-NEW ONE   */
+                    match self.ghost.forget(key).unwrap_or(1).max(initial) {
+                        freq if freq > 1 => self.add_with_freq(node.clone(), freq),
+                        _ => self.add_new(node.clone()),
+                    }
+                    self.count_new(&node);
                     self.keys.insert(key, node);
                 }
             }
+            self.debug_check();
         }
 
         pub fn clear_cache(&mut self) {
-            self.keys.drain();
-            self.freqs.drain();
+            self.keys.clear();
+            self.freqs.clear();
+            self.spare_nodes.clear();
+            self.spare_freqs.clear();
             let new_freq = Rc::new(RefCell::new(Freq::new(1)));
             self.freqs.insert(1, new_freq.clone());
             self.head = Some(new_freq.clone());
-            self.len = 0
+            self.ghost.clear();
+            #[cfg(feature = "std")]
+            if let Some(absent) = self.absent.as_mut() {
+                absent.expires.clear();
+                absent.markers.clear_cache();
+            }
+            if let Some(sliding) = self.sliding.as_mut() {
+                sliding.touched.iter_mut().for_each(Vec::clear);
+            }
+            self.len = 0;
+            self.debug_check();
         }
 
-        fn invalidate(&mut self) {
-            self.keys.remove(
-                &self
-                    .head
-                    .as_ref()
-                    .unwrap()
-                    .borrow()
-                    .head
-                    .as_ref()
-                    .unwrap()
-                    .borrow()
-                    .key,
-            );
+        pub fn remove(&mut self, key: i32) -> Option<i32> {
+            #[cfg(feature = "std")]
+            self.forget_absent(key);
+            self.take(key).map(|(val, _)| val)
+        }
 
-            //if deleting node has a child, we move pointer to the next node and delete pointer to deleting node
-            if self
-                .head
-                .as_ref()
-                .unwrap()
-                .borrow()
-                .head
-                .as_ref()
-                .unwrap()
-                .borrow()
-                .next
-                .is_some()
-            {
-                let next_node = self
-                    .head
-                    .as_ref()
-                    .unwrap()
-                    .borrow()
-                    .head
-                    .as_ref()
-                    .unwrap()
-                    .borrow()
-                    .next
-                    .as_ref()
-                    .unwrap()
-                    .clone();
-                self.head.as_mut().unwrap().borrow_mut().head = Some(next_node);
-                self.head
-                    .as_mut()
-                    .unwrap()
-                    .borrow_mut()
-                    .head
-                    .as_mut()
-                    .unwrap()
-                    .borrow_mut()
-                    .prev = None;
-                return;
+        /// Replaces where the cache reads the time from for absent marker
+        /// expiry, `Window::Time` and logarithmic counter decay, which is
+        /// `Instant::now` by default. Tests use it to move time forward
+        /// without sleeping.
+        #[cfg(feature = "std")]
+        pub fn set_clock(&mut self, clock: fn() -> Instant) {
+            self.clock = clock;
+            if let Some(sliding) = self.sliding.as_mut() {
+                sliding.clock = clock;
             }
-            let removed = self
-                .freqs
-                .remove(&self.head.as_ref().unwrap().borrow().f)
-                .unwrap();
+            if let Some(log) = self.log.as_mut() {
+                log.clock = clock;
+            }
+        }
 
-            if removed.borrow().next.is_some()
-                && removed.borrow().prev.is_some()
-                && removed.borrow().prev.as_ref().unwrap().upgrade().is_some()
-            {
+        /// Enables `put_absent`: up to `capacity` keys can be marked as known
+        /// to be absent, each for `ttl`. Markers do not count towards the
+        /// capacity of the cache itself. Needs `std` for the clock.
+        #[cfg(feature = "std")]
+        pub fn set_negative_caching(&mut self, capacity: i32, ttl: Duration) {
+            self.absent = if capacity > 0 {
+                Some(Box::new(Negative {
+                    ttl,
+                    expires: History::new(),
+                    markers: LFUCache::new(capacity),
+                }))
+            } else {
+                None
+            };
+        }
+
+        /// Records that `key` has no value, so that `lookup` answers `Absent`
+        /// instead of `Miss` until the marker expires or is evicted.
+        /// Does nothing unless negative caching is enabled.
+        #[cfg(feature = "std")]
+        pub fn put_absent(&mut self, key: i32) {
+            if self.absent.is_none() {
+                return;
+            }
+            self.take(key);
+            let now = (self.clock)();
+            let absent = self.absent.as_mut().unwrap();
+            absent.purge(now);
+            if !absent.markers.contains(key) && absent.markers.len == absent.markers.cap {
+                let (victim, _, _) = absent.markers.pop_victim().unwrap();
+                absent.expires.remove(victim);
+            }
+            absent.markers.put(key, 0);
+            absent.insert(key, now);
+        }
+
+        pub fn lookup(&mut self, key: i32) -> Lookup {
+            if self.keys.contains_key(&key) {
+                return Lookup::Hit(self.get(key));
+            }
+            self.tick();
+            self.debug_check();
+            #[cfg(feature = "std")]
+            {
+                let absent = match self.absent.as_mut() {
+                    Some(absent) => absent,
+                    None => return Lookup::Miss,
+                };
+                match absent.expires.get(key) {
+                    Some(expires) if *expires > (self.clock)() => {
+                        absent.markers.get(key);
+                        return Lookup::Absent;
+                    }
+                    Some(_) => self.forget_absent(key),
+                    None => {}
+                }
+            }
+            Lookup::Miss
+        }
+
+        #[cfg(feature = "std")]
+        fn forget_absent(&mut self, key: i32) {
+            if let Some(absent) = self.absent.as_mut() {
+                if absent.expires.remove(key).is_some() {
+                    absent.markers.take(key);
+                }
+            }
+        }
+
+        /*   REMOVE A KEY AND HAND BACK ITS VALUE TOGETHER WITH ITS FREQUENCY   */
+        pub(super) fn take(&mut self, key: i32) -> Option<(i32, i32)> {
+            let node = self.keys.remove(&key)?;
+            let parent = node.borrow_mut().parent.take().unwrap().upgrade().unwrap();
+            parent.borrow_mut().unlink(&node);
+            if parent.borrow().head.is_none() {
+                self.remove_freq(parent);
+            }
+            self.len -= 1;
+            let taken = {
+                let node = node.borrow();
+                (node.val, node.freq)
+            };
+            self.recycle_node(node);
+            self.debug_check();
+            Some(taken)
+        }
+
+        /*   THE KEY `invalidate` WOULD EVICT NEXT, WITH ITS VALUE AND FREQUENCY   */
+        pub(super) fn pop_victim(&mut self) -> Option<(i32, i32, i32)> {
+            let key = self.head.as_ref().unwrap().borrow().head.as_ref()?.borrow().key;
+            self.take(key).map(|(val, freq)| (key, val, freq))
+        }
+
+        /*   INSERT WITHOUT EVICTING, THE CALLER HAS ALREADY MADE ROOM   */
+        pub(super) fn insert_with_freq(&mut self, key: i32, value: i32, freq: i32) {
+            let node = self.new_node(key, value);
+            if freq > 1 {
+                self.add_with_freq(node.clone(), freq);
+            } else {
+                self.add_new(node.clone());
+            }
+            self.count_new(&node);
+            self.keys.insert(key, node);
+            self.len += 1;
+            self.debug_check();
+        }
+
+        /*   FREQUENCY OF THE KEY `invalidate` WOULD EVICT NEXT   */
+        pub(super) fn victim_freq(&self) -> Option<i32> {
+            let head = self.head.as_ref().unwrap().borrow();
+            let freq = head.head.as_ref()?.borrow().freq;
+            Some(freq)
+        }
+
+        pub(super) fn freq_of(&self, key: i32) -> Option<i32> {
+            self.keys.get(&key).map(|node| node.borrow().freq)
+        }
+
+        pub(super) fn contains(&self, key: i32) -> bool {
+            self.keys.contains_key(&key)
+        }
+
+        /// Walks the whole structure and reports the first broken link:
+        /// the bucket chain must be ascending and match `freqs`, every
+        /// bucket's ends must match its node list, every node's `parent`
+        /// and `freq` must match its bucket, `prev` must mirror `next`, and
+        /// `keys` must hold exactly the `len` nodes of the chain.
+        pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+            if self.keys.len() != self.len.max(0) as usize || self.len < 0 {
+                return Err(InvariantViolation::Length {
+                    keys: self.keys.len(),
+                    len: self.len,
+                });
+            }
+            let head = self.head.clone().ok_or(InvariantViolation::Head)?;
+            if head
+                .borrow()
+                .prev
+                .as_ref()
+                .and_then(Weak::upgrade)
+                .is_some()
+            {
+                return Err(InvariantViolation::Head);
+            }
+            let mut seen_freqs = 0;
+            let mut seen_keys = 0;
+            let mut prev_bucket: Option<Rc<RefCell<Freq>>> = None;
+            let mut bucket = Some(head);
+            while let Some(b) = bucket {
+                let freq = b.borrow();
+                let f = freq.f;
+                if let Some(prev) = prev_bucket.as_ref() {
+                    if prev.borrow().f >= f {
+                        return Err(InvariantViolation::Order {
+                            prev: prev.borrow().f,
+                            freq: f,
+                        });
+                    }
+                    match freq.prev.as_ref().and_then(Weak::upgrade) {
+                        Some(p) if Rc::ptr_eq(&p, prev) => {}
+                        _ => return Err(InvariantViolation::Links { freq: f }),
+                    }
+                }
+                match self.freqs.get(&f) {
+                    Some(indexed) if Rc::ptr_eq(indexed, &b) => {}
+                    _ => return Err(InvariantViolation::FreqIndex { freq: f }),
+                }
+                seen_freqs += 1;
+                if seen_freqs > self.freqs.len() {
+                    return Err(InvariantViolation::FreqIndex { freq: f });
+                }
+                let lone_empty_head = self.len == 0 && prev_bucket.is_none() && freq.next.is_none();
+                if freq.head.is_none() && !lone_empty_head {
+                    return Err(InvariantViolation::EmptyBucket { freq: f });
+                }
+
+                let mut prev_node: Option<Rc<RefCell<Node>>> = None;
+                let mut node = freq.head.clone();
+                while let Some(n) = node {
+                    let current = n.borrow();
+                    let prev = current.prev.as_ref().and_then(Weak::upgrade);
+                    let mirrored = match (prev.as_ref(), prev_node.as_ref()) {
+                        (Some(prev), Some(expected)) => Rc::ptr_eq(prev, expected),
+                        (None, None) => true,
+                        _ => false,
+                    };
+                    if !mirrored {
+                        return Err(InvariantViolation::Links { freq: f });
+                    }
+                    match current.parent.as_ref().and_then(Weak::upgrade) {
+                        Some(parent) if Rc::ptr_eq(&parent, &b) => {}
+                        _ => {
+                            return Err(InvariantViolation::Parent {
+                                key: current.key,
+                                freq: f,
+                            })
+                        }
+                    }
+                    if current.freq != f {
+                        return Err(InvariantViolation::Frequency {
+                            key: current.key,
+                            freq: current.freq,
+                            bucket: f,
+                        });
+                    }
+                    match self.keys.get(&current.key) {
+                        Some(indexed) if Rc::ptr_eq(indexed, &n) => {}
+                        _ => return Err(InvariantViolation::KeyIndex { key: current.key }),
+                    }
+                    seen_keys += 1;
+                    if seen_keys > self.keys.len() {
+                        return Err(InvariantViolation::Links { freq: f });
+                    }
+                    node = current.next.clone();
+                    drop(current);
+                    prev_node = Some(n);
+                }
+                let tail_matches = match (freq.tail.as_ref(), prev_node.as_ref()) {
+                    (Some(tail), Some(last)) => Rc::ptr_eq(tail, last),
+                    (None, None) => true,
+                    _ => false,
+                };
+                if !tail_matches {
+                    return Err(InvariantViolation::Ends { freq: f });
+                }
+
+                bucket = freq.next.clone();
+                drop(freq);
+                prev_bucket = Some(b);
+            }
+
+            /*
+                EVERYTHING IN THE CHAIN IS INDEXED, SO ANY EXTRA INDEX ENTRY IS A STRAY.
+                ONLY THEN IS THE CHAIN COLLECTED, SO A PASSING CHECK ALLOCATES NOTHING
+            */
+            if seen_freqs != self.freqs.len() || seen_keys != self.keys.len() {
+                let (mut seen_freqs, mut seen_keys) = self.chain();
+                seen_freqs.sort_unstable();
+                let stray = self
+                    .freqs
+                    .iter()
+                    .find(|(f, _)| seen_freqs.binary_search(f).is_err());
+                if let Some((&stray, _)) = stray {
+                    return Err(InvariantViolation::FreqIndex { freq: stray });
+                }
+                seen_keys.sort_unstable();
+                let (&stray, _) = self
+                    .keys
+                    .iter()
+                    .find(|(key, _)| seen_keys.binary_search(key).is_err())
+                    .unwrap();
+                return Err(InvariantViolation::KeyIndex { key: stray });
+            }
+            Ok(())
+        }
+
+        /*   FREQUENCIES AND KEYS IN CHAIN ORDER, FOR REPORTING A STRAY INDEX ENTRY   */
+        fn chain(&self) -> (Vec<i32>, Vec<i32>) {
+            let (mut freqs, mut keys) = (Vec::new(), Vec::new());
+            let mut bucket = self.head.clone();
+            while let Some(b) = bucket {
+                freqs.push(b.borrow().f);
+                let mut node = b.borrow().head.clone();
+                while let Some(n) = node {
+                    keys.push(n.borrow().key);
+                    node = n.borrow().next.clone();
+                }
+                bucket = b.borrow().next.clone();
+            }
+            (freqs, keys)
+        }
+
+        /*   EVERY MUTATING ENTRY POINT ENDS WITH THIS, SO DEBUG BUILDS CATCH A BROKEN LINK AT ITS SOURCE   */
+        fn debug_check(&self) {
+            if cfg!(debug_assertions) {
+                if let Err(violation) = self.check_invariants() {
+                    panic!("LFUCache invariant violated: {}", violation);
+                }
+            }
+        }
+
+        /*   UNLINK AN EMPTY BUCKET FROM THE CHAIN, KEEPING AN EMPTY HEAD IF IT WAS THE LAST ONE   */
+        fn remove_freq(&mut self, freq: Rc<RefCell<Freq>>) {
+            self.freqs.remove(&freq.borrow().f);
+            let prev = freq.borrow_mut().prev.take().and_then(|prev| prev.upgrade());
+            let next = freq.borrow_mut().next.take();
+            match (prev, next) {
+                (Some(prev), next) => {
+                    if let Some(next) = next.as_ref() {
+                        next.borrow_mut().prev = Some(Rc::downgrade(&prev));
+                    }
+                    prev.borrow_mut().next = next;
+                }
+                (None, Some(next)) => {
+                    next.borrow_mut().prev = None;
+                    self.head = Some(next);
+                }
+                (None, None) => {
+                    let new_freq = self.new_freq(1);
+                    self.freqs.insert(1, new_freq.clone());
+                    self.head = Some(new_freq);
+                }
+            }
+            self.recycle_freq(freq);
+        }
+
+        /*
+            EVICTED NODES AND EMPTIED BUCKETS ARE KEPT AND HANDED OUT AGAIN, SO A FULL
+            CACHE SWAPS ONE ENTRY FOR ANOTHER WITHOUT TOUCHING THE ALLOCATOR.
+            ONLY AN Rc NOTHING ELSE POINTS TO IS KEPT: A STALE Weak MUST NOT COME BACK
+            TO LIFE AS A LINK TO SOMETHING REUSED. NEITHER LIST GROWS PAST THE CAPACITY
+        */
+        fn new_node(&mut self, key: i32, value: i32) -> Rc<RefCell<Node>> {
+            match self.spare_nodes.pop() {
+                Some(node) => {
+                    {
+                        let mut n = node.borrow_mut();
+                        let counts = core::mem::take(&mut n.counts);
+                        *n = Node {
+                            counts,
+                            ..Node::new(key, value)
+                        };
+                    }
+                    node
+                }
+                None => {
+                    self.stock();
+                    Rc::new(RefCell::new(Node::new(key, value)))
+                }
+            }
+        }
+
+        /*
+            A NODE IS ONLY EVER ALLOCATED WHILE THE CACHE FILLS UP. NO MORE THAN ONE BUCKET
+            PER KEY PLUS THE HEAD IS EVER LIVE, SO BUCKETS, LIVE AND SPARE, ARE TOPPED UP TO
+            THAT MANY, AND A FULL CACHE NEVER ALLOCATES HOWEVER ITS FREQUENCIES SPREAD
+        */
+        fn stock(&mut self) {
+            let room = self.keys.len() + 2;
+            if self.spare_freqs.len() + self.freqs.len() < room {
+                self.spare_freqs
+                    .reserve(room.saturating_sub(self.spare_freqs.len()));
+                self.spare_freqs.push(Rc::new(RefCell::new(Freq::new(0))));
+            }
+            self.spare_nodes
+                .reserve(room.saturating_sub(self.spare_nodes.len()));
+            self.freqs.reserve(room.saturating_sub(self.freqs.len()));
+            self.keys.reserve(room.saturating_sub(self.keys.len()));
+        }
+
+        fn new_freq(&mut self, frequency: i32) -> Rc<RefCell<Freq>> {
+            match self.spare_freqs.pop() {
+                Some(freq) => {
+                    freq.borrow_mut().f = frequency;
+                    freq
+                }
+                None => Rc::new(RefCell::new(Freq::new(frequency))),
+            }
+        }
+
+        /*   THE NODE MUST ALREADY BE OUT OF ITS BUCKET AND OF `keys`   */
+        fn recycle_node(&mut self, node: Rc<RefCell<Node>>) {
+            {
+                let mut n = node.borrow_mut();
+                n.next = None;
+                n.prev = None;
+                n.parent = None;
+                n.counts.clear();
+            }
+            if Rc::strong_count(&node) == 1
+                && Rc::weak_count(&node) == 0
+                && self.spare_nodes.len() < self.cap.max(0) as usize
+            {
+                self.spare_nodes.push(node);
+            }
+        }
+
+        /*   THE BUCKET MUST ALREADY BE OUT OF THE CHAIN AND OF `freqs`   */
+        fn recycle_freq(&mut self, freq: Rc<RefCell<Freq>>) {
+            *freq.borrow_mut() = Freq::new(0);
+            if Rc::strong_count(&freq) == 1
+                && Rc::weak_count(&freq) == 0
+                && self.spare_freqs.len() + self.freqs.len() <= self.cap.max(0) as usize
+            {
+                self.spare_freqs.push(freq);
+            }
+        }
+
+        /// Switches between evicting one key per insert and evicting in
+        /// batches between a high and a low watermark. `high` is capped at
+        /// the capacity and `low` at `high - 1`.
+        pub fn set_eviction(&mut self, eviction: Eviction) {
+            let clamp = |high: i32, low: i32| {
+                let high = high.min(self.cap).max(1);
+                (high, low.min(high - 1).max(0))
+            };
+            self.eviction = match eviction {
+                Eviction::Single => Eviction::Single,
+                Eviction::Batch { high, low } => {
+                    let (high, low) = clamp(high, low);
+                    Eviction::Batch { high, low }
+                }
+                Eviction::Deferred { high, low } => {
+                    let (high, low) = clamp(high, low);
+                    Eviction::Deferred { high, low }
+                }
+            };
+        }
+
+        /// Changes the capacity, evicting the least frequently used keys
+        /// until the cache fits. Watermarks are clamped to the new capacity.
+        pub fn resize(&mut self, capacity: i32) {
+            self.cap = capacity.max(0);
+            if self.len > self.cap {
+                self.evict_batch(self.len - self.cap);
+            }
+            self.spare_nodes.truncate(self.cap as usize);
+            self.spare_freqs.truncate(self.cap as usize);
+            self.set_eviction(self.eviction);
+            self.debug_check();
+        }
+
+        /// Evicts down to the low watermark if the length is past the high
+        /// one. Returns how many keys were evicted.
+        /// With logarithmic counters it first applies pending decay to every
+        /// key, which otherwise only happens when a key is accessed or
+        /// reached by the eviction sweep.
+        pub fn maintain(&mut self) -> i32 {
+            #[cfg(feature = "std")]
+            if self.log.is_some() {
+                let nodes: Vec<Rc<RefCell<Node>>> = self.keys.values().cloned().collect();
+                for node in nodes.iter() {
+                    self.decay(node);
+                }
+            }
+            let evicted = match self.eviction {
+                Eviction::Batch { high, low } | Eviction::Deferred { high, low }
+                    if self.len > high =>
+                {
+                    let count = self.len - low;
+                    self.evict_batch(count);
+                    count
+                }
+                _ => 0,
+            };
+            self.debug_check();
+            evicted
+        }
+
+        /*
+            EVICT `count` KEYS IN ONE WALK FROM HEAD. BUCKETS THAT RUN EMPTY ARE
+            DROPPED AS WE GO AND ONLY THE FIRST SURVIVING ONE IS RELINKED AS HEAD
+        */
+        fn evict_batch(&mut self, count: i32) {
+            #[cfg(feature = "std")]
+            self.sweep();
+            let mut bucket = self.head.take().unwrap();
+            let mut evicted = 0;
+            loop {
+                if bucket.borrow().head.is_none() {
+                    self.freqs.remove(&bucket.borrow().f);
+                    let next = bucket.borrow_mut().next.take();
+                    match next {
+                        Some(next) => {
+                            next.borrow_mut().prev = None;
+                            let empty = core::mem::replace(&mut bucket, next);
+                            self.recycle_freq(empty);
+                            continue;
+                        }
+                        None => {
+                            let fresh = self.new_freq(1);
+                            self.freqs.insert(1, fresh.clone());
+                            let empty = core::mem::replace(&mut bucket, fresh);
+                            self.recycle_freq(empty);
+                            break;
+                        }
+                    }
+                }
+                if evicted == count {
+                    break;
+                }
+                let node = bucket.borrow_mut().pop_front().unwrap();
+                let (key, freq) = {
+                    let node = node.borrow();
+                    (node.key, node.freq)
+                };
+                self.keys.remove(&key);
+                self.ghost.remember(key, freq);
+                self.recycle_node(node);
+                evicted += 1;
+            }
+            bucket.borrow_mut().prev = None;
+            self.head = Some(bucket);
+            self.len -= evicted;
+        }
+
+        /// Evicts every key with a frequency below `freq` and returns them in
+        /// eviction order. Buckets are unlinked from the chain whole.
+        pub fn evict_below(&mut self, freq: i32) -> Vec<(i32, i32)> {
+            let mut evicted = Vec::new();
+            loop {
+                let bucket = self.head.clone().unwrap();
+                if bucket.borrow().head.is_none() || bucket.borrow().f >= freq {
+                    break;
+                }
+
+                /*   DETACH THE WHOLE BUCKET FIRST, THEN SWEEP ITS NODES   */
+                self.freqs.remove(&bucket.borrow().f);
+                let next = bucket.borrow_mut().next.take();
+                match next {
+                    Some(next) => {
+                        next.borrow_mut().prev = None;
+                        self.head = Some(next);
+                    }
+                    None => {
+                        let new_freq = self.new_freq(1);
+                        self.freqs.insert(1, new_freq.clone());
+                        self.head = Some(new_freq);
+                    }
+                }
+
+                bucket.borrow_mut().tail = None;
+                let mut node = bucket.borrow_mut().head.take();
+                while let Some(n) = node {
+                    let (key, val, f) = {
+                        let n = n.borrow();
+                        (n.key, n.val, n.freq)
+                    };
+                    self.keys.remove(&key);
+                    self.ghost.remember(key, f);
+                    evicted.push((key, val));
+                    node = n.borrow_mut().next.take();
+                    if let Some(next) = node.as_ref() {
+                        next.borrow_mut().prev = None;
+                    }
+                    self.recycle_node(n);
+                }
+                self.recycle_freq(bucket);
+            }
+            self.len -= evicted.len() as i32;
+            self.debug_check();
+            evicted
+        }
+
+        fn invalidate(&mut self) {
+            #[cfg(feature = "std")]
+            self.sweep();
+            let (victim, freq) = {
+                let head = self.head.as_ref().unwrap().borrow();
+                let node = head.head.as_ref().unwrap().borrow();
+                (node.key, node.freq)
+            };
+            self.ghost.remember(victim, freq);
+
+            let node = self.keys.remove(&victim).unwrap();
+
+            //if deleting node has a child, we move pointer to the next node and delete pointer to deleting node
+            if self
+                .head
+                .as_ref()
+                .unwrap()
+                .borrow()
+                .head
+                .as_ref()
+                .unwrap()
+                .borrow()
+                .next
+                .is_some()
+            {
+                let next_node = self
+                    .head
+                    .as_ref()
+                    .unwrap()
+                    .borrow()
+                    .head
+                    .as_ref()
+                    .unwrap()
+                    .borrow()
+                    .next
+                    .as_ref()
+                    .unwrap()
+                    .clone();
+                self.head.as_mut().unwrap().borrow_mut().head = Some(next_node);
+                self.head
+                    .as_mut()
+                    .unwrap()
+                    .borrow_mut()
+                    .head
+                    .as_mut()
+                    .unwrap()
+                    .borrow_mut()
+                    .prev = None;
+                self.recycle_node(node);
+                return;
+            }
+            let removed = self
+                .freqs
+                .remove(&self.head.as_ref().unwrap().borrow().f)
+                .unwrap();
+            removed.borrow_mut().head = None;
+            removed.borrow_mut().tail = None;
+
+            if removed.borrow().next.is_some()
+                && removed.borrow().prev.is_some()
+                && removed.borrow().prev.as_ref().unwrap().upgrade().is_some()
+            {
                 let prev = removed
                     .borrow_mut()
                     .prev
@@ -550,15 +1932,17 @@ pub mod LFU {
                 self.head = Some(next_head.borrow().next.as_ref().unwrap().clone());
                 self.head.as_mut().unwrap().borrow_mut().prev = None;
             } else {
-                let freq = Rc::new(RefCell::new(Freq::new(1)));
+                let freq = self.new_freq(1);
                 self.head = Some(freq.clone());
                 self.freqs.insert(1, freq);
             }
+            self.recycle_node(node);
+            self.recycle_freq(removed);
         }
 
         fn add_new(&mut self, node: Rc<RefCell<Node>>) {
             if self.head.as_ref().unwrap().borrow().f != 1 {
-                let freq_one = Rc::new(RefCell::new(Freq::new(1)));
+                let freq_one = self.new_freq(1);
                 self.freqs.insert(1, freq_one.clone());
                 freq_one.borrow_mut().next = Some(self.head.as_ref().unwrap().clone());
                 self.head.as_mut().unwrap().borrow_mut().prev = Some(Rc::downgrade(&freq_one));
@@ -593,5 +1977,3436 @@ pub mod LFU {
                 self.head.as_mut().unwrap().borrow_mut().head = Some(node)
             }
         }
+
+        /*
+            ATTACH A NODE STRAIGHT TO THE BUCKET OF THE GIVEN FREQUENCY.
+            IF THERE IS NO SUCH BUCKET WE WALK THE CHAIN FROM HEAD TO FIND
+            WHERE IT BELONGS, SO THIS IS O(NUMBER OF BUCKETS), NOT O(1)
+        */
+        fn add_with_freq(&mut self, node: Rc<RefCell<Node>>, freq: i32) {
+            node.borrow_mut().freq = freq;
+
+            let parent = match self.freqs.get(&freq) {
+                Some(parent) => parent.clone(),
+                None => {
+                    let parent = self.new_freq(freq);
+                    self.freqs.insert(freq, parent.clone());
+
+                    if self.head.as_ref().unwrap().borrow().head.is_none() {
+                        /*   THE CACHE IS EMPTY, SO THE NEW BUCKET REPLACES THE EMPTY HEAD   */
+                        let empty = self.head.replace(parent.clone()).unwrap();
+                        self.freqs.remove(&empty.borrow().f);
+                        self.recycle_freq(empty);
+                    } else if self.head.as_ref().unwrap().borrow().f > freq {
+                        let old_head = self.head.replace(parent.clone()).unwrap();
+                        old_head.borrow_mut().prev = Some(Rc::downgrade(&parent));
+                        parent.borrow_mut().next = Some(old_head);
+                    } else {
+                        /*   FIND THE LAST BUCKET WITH A LOWER FREQUENCY   */
+                        let mut prev = self.head.as_ref().unwrap().clone();
+                        loop {
+                            let next = prev.borrow().next.clone();
+                            match next {
+                                Some(next) if next.borrow().f < freq => prev = next,
+                                _ => break,
+                            }
+                        }
+
+                        let next = prev.borrow_mut().next.take();
+                        if let Some(next) = next.as_ref() {
+                            next.borrow_mut().prev = Some(Rc::downgrade(&parent));
+                        }
+                        parent.borrow_mut().next = next;
+                        parent.borrow_mut().prev = Some(Rc::downgrade(&prev));
+                        prev.borrow_mut().next = Some(parent.clone());
+                    }
+                    parent
+                }
+            };
+
+            parent.borrow_mut().push_back(node.clone());
+            node.borrow_mut().parent = Some(Rc::downgrade(&parent));
+        }
+    }
+
+    impl Cache<i32, i32> for LFUCache {
+        fn get(&mut self, key: &i32) -> Option<i32> {
+            match self.lookup(*key) {
+                Lookup::Hit(val) => Some(val),
+                _ => None,
+            }
+        }
+
+        fn put(&mut self, key: i32, value: i32) {
+            LFUCache::put(self, key, value)
+        }
+
+        fn remove(&mut self, key: &i32) -> Option<i32> {
+            LFUCache::remove(self, *key)
+        }
+
+        fn len(&self) -> usize {
+            self.len as usize
+        }
+
+        fn capacity(&self) -> usize {
+            self.cap.max(0) as usize
+        }
+
+        fn clear(&mut self) {
+            self.clear_cache()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{Eviction, Freq, InvariantViolation, LFUCache};
+        use alloc::rc::Rc;
+        use alloc::vec;
+        use alloc::vec::Vec;
+        use core::cell::RefCell;
+
+        /*   BUCKET 1 HOLDS 2 AND 3, BUCKET 2 HOLDS 1   */
+        fn cache() -> LFUCache {
+            let mut cache = LFUCache::new(4);
+            cache.put(1, 1);
+            cache.put(2, 2);
+            cache.put(3, 3);
+            cache.get(1);
+            assert_eq!(cache.check_invariants(), Ok(()));
+            cache
+        }
+
+        /*   KEY k IS READ k TIMES, SO ITS FREQUENCY IS k + 1   */
+        fn read_by_key(cache: &mut LFUCache, keys: i32) {
+            for key in 0..keys {
+                cache.put(key, key);
+                for _ in 0..key {
+                    cache.get(key);
+                }
+            }
+        }
+
+        #[test]
+        fn evicted_key_resumes_its_remembered_frequency() {
+            let mut cache = LFUCache::with_ghost(1, 2);
+            cache.put(1, 1);
+            for _ in 0..4 {
+                cache.get(1);
+            }
+            cache.put(2, 2);
+            assert_eq!(cache.get(1), -1);
+            cache.put(1, 1);
+            assert_eq!(cache.keys[&1].borrow().freq, 5);
+            assert_eq!(cache.check_invariants(), Ok(()));
+
+            /*   3 WAS NEVER EVICTED, SO IT STARTS OVER   */
+            cache.put(3, 3);
+            assert_eq!(cache.keys[&3].borrow().freq, 1);
+        }
+
+        #[test]
+        fn ghost_stays_within_its_capacity() {
+            let mut cache = LFUCache::with_ghost(1, 3);
+            for i in 0..200 {
+                cache.put(i % 5, i);
+                cache.get(i % 5);
+                assert!(cache.ghost.freqs.len() <= 3);
+                assert!(cache.ghost.freqs.order.len() <= 6);
+            }
+
+            /*   ONLY THE THREE MOST RECENTLY EVICTED KEYS ARE REMEMBERED   */
+            let mut remembered: Vec<i32> = (0..5)
+                .filter(|key| cache.ghost.forget(*key).is_some())
+                .collect();
+            remembered.sort_unstable();
+            assert_eq!(remembered, vec![1, 2, 3]);
+        }
+
+        #[test]
+        fn batch_eviction_drops_to_low_once_high_is_reached() {
+            let mut cache = LFUCache::new(10);
+            cache.set_eviction(Eviction::Batch { high: 8, low: 4 });
+            read_by_key(&mut cache, 8);
+            assert_eq!(cache.len, 8);
+
+            cache.put(100, 100);
+            assert_eq!(cache.len, 5);
+            for key in 0..4 {
+                assert!(!cache.keys.contains_key(&key));
+            }
+            for key in (4..8).chain(Some(100)) {
+                assert!(cache.keys.contains_key(&key));
+            }
+            assert_eq!(cache.check_invariants(), Ok(()));
+        }
+
+        #[test]
+        fn deferred_eviction_waits_for_maintain_or_a_full_cache() {
+            let mut cache = LFUCache::new(8);
+            cache.set_eviction(Eviction::Deferred { high: 6, low: 3 });
+            read_by_key(&mut cache, 8);
+            assert_eq!(cache.len, 8);
+
+            /*   THE LEAST FREQUENT FIVE GO, LOWEST FREQUENCY FIRST   */
+            assert_eq!(cache.maintain(), 5);
+            assert_eq!(cache.len, 3);
+            for key in 0..8 {
+                assert_eq!(cache.keys.contains_key(&key), key >= 5);
+            }
+            assert_eq!(cache.maintain(), 0);
+
+            /*   PAST `high` BUT NOT FULL: NOTHING HAPPENS UNTIL THE CACHE FILLS   */
+            for key in 10..15 {
+                cache.put(key, key);
+            }
+            assert_eq!(cache.len, 8);
+            cache.put(20, 20);
+            assert_eq!(cache.len, 4);
+            for key in (5..8).chain(Some(20)) {
+                assert!(cache.keys.contains_key(&key));
+            }
+            assert_eq!(cache.check_invariants(), Ok(()));
+        }
+
+        #[test]
+        fn evict_below_takes_whole_buckets_under_the_threshold() {
+            let mut cache = LFUCache::new(6);
+            for key in 0..6 {
+                cache.put(key, key * 10);
+                for _ in 0..key % 3 {
+                    cache.get(key);
+                }
+            }
+
+            assert_eq!(cache.evict_below(1), vec![]);
+            assert_eq!(
+                cache.evict_below(3),
+                vec![(0, 0), (3, 30), (1, 10), (4, 40)]
+            );
+            assert_eq!(cache.len, 2);
+            assert_eq!(cache.head.as_ref().unwrap().borrow().f, 3);
+            assert!(!cache.freqs.contains_key(&1) && !cache.freqs.contains_key(&2));
+            assert_eq!(cache.get(2), 20);
+            assert_eq!(cache.get(5), 50);
+            assert_eq!(cache.check_invariants(), Ok(()));
+
+            /*   EMPTIED ALL THE WAY, THE CACHE IS BACK TO ITS EMPTY HEAD AND STILL USABLE   */
+            assert_eq!(cache.evict_below(100), vec![(2, 20), (5, 50)]);
+            assert_eq!(cache.len, 0);
+            assert_eq!(cache.check_invariants(), Ok(()));
+            cache.put(7, 7);
+            assert_eq!(cache.get(7), 7);
+            assert_eq!(cache.check_invariants(), Ok(()));
+        }
+
+        /*   A FULL CACHE HAS AT MOST ONE BUCKET PER KEY, AN EMPTY ONE ITS EMPTY HEAD   */
+        #[test]
+        fn spare_buckets_never_outnumber_what_can_be_live() {
+            let mut cache = LFUCache::new(64);
+            for key in 0..1000 {
+                cache.put(key, key);
+                cache.get(key % 7);
+                if key % 3 == 0 {
+                    cache.remove(key);
+                }
+                if key % 50 == 0 {
+                    cache.evict_below(3);
+                }
+                assert!(cache.spare_freqs.len() + cache.freqs.len() <= 65);
+            }
+        }
+
+        #[test]
+        fn wrong_parent_is_reported() {
+            let cache = cache();
+            let bucket_one = Rc::downgrade(&cache.freqs[&1]);
+            cache.keys[&1].borrow_mut().parent = Some(bucket_one);
+            assert_eq!(
+                cache.check_invariants(),
+                Err(InvariantViolation::Parent { key: 1, freq: 2 })
+            );
+        }
+
+        #[test]
+        fn wrong_frequency_is_reported() {
+            let cache = cache();
+            cache.keys[&2].borrow_mut().freq = 5;
+            assert_eq!(
+                cache.check_invariants(),
+                Err(InvariantViolation::Frequency {
+                    key: 2,
+                    freq: 5,
+                    bucket: 1
+                })
+            );
+        }
+
+        #[test]
+        fn missing_and_stray_freqs_entries_are_reported() {
+            let mut cache = cache();
+            let bucket_two = cache.freqs.remove(&2).unwrap();
+            assert_eq!(
+                cache.check_invariants(),
+                Err(InvariantViolation::FreqIndex { freq: 2 })
+            );
+            cache.freqs.insert(2, bucket_two);
+            cache.freqs.insert(7, Rc::new(RefCell::new(Freq::new(7))));
+            assert_eq!(
+                cache.check_invariants(),
+                Err(InvariantViolation::FreqIndex { freq: 7 })
+            );
+        }
+
+        #[test]
+        fn unindexed_key_is_reported() {
+            let mut cache = cache();
+            cache.keys.remove(&3);
+            assert_eq!(
+                cache.check_invariants(),
+                Err(InvariantViolation::Length { keys: 2, len: 3 })
+            );
+            cache.len = 2;
+            assert_eq!(
+                cache.check_invariants(),
+                Err(InvariantViolation::KeyIndex { key: 3 })
+            );
+        }
+
+        #[test]
+        fn swapped_buckets_are_reported() {
+            let mut cache = cache();
+            let one = cache.freqs[&1].clone();
+            let two = cache.freqs[&2].clone();
+            one.borrow_mut().next = None;
+            one.borrow_mut().prev = Some(Rc::downgrade(&two));
+            two.borrow_mut().prev = None;
+            two.borrow_mut().next = Some(one);
+            cache.head = Some(two);
+            assert_eq!(
+                cache.check_invariants(),
+                Err(InvariantViolation::Order { prev: 2, freq: 1 })
+            );
+        }
+
+        #[test]
+        fn broken_prev_links_are_reported() {
+            let cache = cache();
+            let prev = cache.keys[&3].borrow_mut().prev.take();
+            assert_eq!(
+                cache.check_invariants(),
+                Err(InvariantViolation::Links { freq: 1 })
+            );
+            cache.keys[&3].borrow_mut().prev = prev;
+            cache.freqs[&2].borrow_mut().prev = None;
+            assert_eq!(
+                cache.check_invariants(),
+                Err(InvariantViolation::Links { freq: 2 })
+            );
+        }
+
+        #[test]
+        fn wrong_tail_is_reported() {
+            let cache = cache();
+            cache.freqs[&1].borrow_mut().tail = Some(cache.keys[&2].clone());
+            assert_eq!(
+                cache.check_invariants(),
+                Err(InvariantViolation::Ends { freq: 1 })
+            );
+        }
+
+        #[test]
+        fn empty_bucket_in_the_chain_is_reported() {
+            let mut cache = cache();
+            let two = cache.freqs[&2].clone();
+            let empty = Rc::new(RefCell::new(Freq::new(5)));
+            empty.borrow_mut().prev = Some(Rc::downgrade(&two));
+            two.borrow_mut().next = Some(empty.clone());
+            cache.freqs.insert(5, empty);
+            assert_eq!(
+                cache.check_invariants(),
+                Err(InvariantViolation::EmptyBucket { freq: 5 })
+            );
+        }
+    }
+}
+
+/*
+    ADAPTIVE REPLACEMENT CACHE (MEGIDDO & MODHA).
+    T1 HOLDS KEYS SEEN ONCE RECENTLY, T2 KEYS SEEN AT LEAST TWICE.
+    B1 AND B2 ARE GHOST LISTS OF KEYS EVICTED FROM T1 AND T2.
+    A HIT IN B1 GROWS THE TARGET SIZE `p` OF T1, A HIT IN B2 SHRINKS IT.
+    EVERY LIST IS A `Freq` BUCKET USED AS A PLAIN LRU LIST: HEAD IS THE
+    LEAST RECENTLY USED NODE, TAIL THE MOST RECENTLY USED ONE
+*/
+#[allow(non_snake_case)]
+pub mod ARC {
+    use super::Cache;
+    use super::LFU::{Freq, Node};
+    use crate::table::HashMap;
+    use alloc::rc::Rc;
+    use core::cell::RefCell;
+    use core::fmt;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum List {
+        T1,
+        T2,
+        B1,
+        B2,
+    }
+
+    #[derive(Debug)]
+    pub struct ARCCache {
+        cap: i32,
+        p: i32,
+        keys: HashMap<i32, (Rc<RefCell<Node>>, List)>,
+        lists: [Freq; 4],
+        lens: [i32; 4],
+    }
+
+    impl fmt::Display for ARCCache {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "length: {}", self.len_of(List::T1) + self.len_of(List::T2))?;
+            writeln!(f, "capacity: {}", self.cap)?;
+            write!(f, "target: {}", self.p)?;
+            for list in [List::T1, List::T2, List::B1, List::B2].iter() {
+                write!(f, "\n{:?} :", list)?;
+                let mut node = self.lists[*list as usize].head.clone();
+                while let Some(n) = node {
+                    match list {
+                        List::T1 | List::T2 => write!(
+                            f,
+                            " [ key: {}, value: {} ]",
+                            n.borrow().key,
+                            n.borrow().val
+                        )?,
+                        List::B1 | List::B2 => write!(f, " [ key: {} ]", n.borrow().key)?,
+                    }
+                    node = n.borrow().next.clone();
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl ARCCache {
+        pub fn new(capacity: i32) -> Self {
+            ARCCache {
+                cap: capacity,
+                p: 0,
+                keys: HashMap::new(),
+                lists: [Freq::new(0), Freq::new(0), Freq::new(0), Freq::new(0)],
+                lens: [0; 4],
+            }
+        }
+
+        fn len_of(&self, list: List) -> i32 {
+            self.lens[list as usize]
+        }
+
+        fn push(&mut self, list: List, node: Rc<RefCell<Node>>) {
+            let key = node.borrow().key;
+            self.lists[list as usize].push_back(node.clone());
+            self.lens[list as usize] += 1;
+            self.keys.insert(key, (node, list));
+        }
+
+        fn take(&mut self, key: i32) -> Option<(Rc<RefCell<Node>>, List)> {
+            let (node, list) = self.keys.remove(&key)?;
+            self.lists[list as usize].unlink(&node);
+            self.lens[list as usize] -= 1;
+            Some((node, list))
+        }
+
+        fn pop_lru(&mut self, list: List) -> Option<Rc<RefCell<Node>>> {
+            let node = self.lists[list as usize].pop_front()?;
+            self.lens[list as usize] -= 1;
+            self.keys.remove(&node.borrow().key);
+            Some(node)
+        }
+
+        /*   MOVE ONE RESIDENT KEY INTO ITS GHOST LIST, WHICH ONLY KEEPS THE KEY   */
+        fn replace(&mut self, hit_in_b2: bool) {
+            let t1 = self.len_of(List::T1);
+            let (node, ghost) = if t1 >= 1 && ((hit_in_b2 && t1 == self.p) || t1 > self.p) {
+                (self.pop_lru(List::T1), List::B1)
+            } else if let Some(node) = self.pop_lru(List::T2) {
+                (Some(node), List::B2)
+            } else {
+                (self.pop_lru(List::T1), List::B1)
+            };
+            if let Some(node) = node {
+                node.borrow_mut().val = 0;
+                self.push(ghost, node);
+            }
+        }
+
+        /*   A GHOST HIT ONLY MAKES ROOM WHEN THE RESIDENT LISTS ARE FULL, `remove` CAN LEAVE THEM SHORT   */
+        fn resident_full(&self) -> bool {
+            self.len_of(List::T1) + self.len_of(List::T2) >= self.cap
+        }
+
+        pub fn get(&mut self, key: i32) -> i32 {
+            match self.keys.get(&key).map(|entry| entry.1) {
+                Some(List::T1) | Some(List::T2) => {
+                    let (node, _) = self.take(key).unwrap();
+                    let val = node.borrow().val;
+                    self.push(List::T2, node);
+                    val
+                }
+                _ => -1,
+            }
+        }
+
+        pub fn put(&mut self, key: i32, value: i32) {
+            if self.cap <= 0 {
+                return;
+            }
+            match self.keys.get(&key).map(|entry| entry.1) {
+                Some(List::T1) | Some(List::T2) => {
+                    let (node, _) = self.take(key).unwrap();
+                    node.borrow_mut().val = value;
+                    self.push(List::T2, node);
+                }
+                Some(List::B1) => {
+                    let delta = (self.len_of(List::B2) / self.len_of(List::B1)).max(1);
+                    self.p = (self.p + delta).min(self.cap);
+                    let (node, _) = self.take(key).unwrap();
+                    if self.resident_full() {
+                        self.replace(false);
+                    }
+                    node.borrow_mut().val = value;
+                    self.push(List::T2, node);
+                }
+                Some(List::B2) => {
+                    let delta = (self.len_of(List::B1) / self.len_of(List::B2)).max(1);
+                    self.p = (self.p - delta).max(0);
+                    let (node, _) = self.take(key).unwrap();
+                    if self.resident_full() {
+                        self.replace(true);
+                    }
+                    node.borrow_mut().val = value;
+                    self.push(List::T2, node);
+                }
+                None => {
+                    let l1 = self.len_of(List::T1) + self.len_of(List::B1);
+                    let total: i32 = self.lens.iter().sum();
+                    if l1 == self.cap {
+                        if self.len_of(List::T1) < self.cap {
+                            self.pop_lru(List::B1);
+                            if self.resident_full() {
+                                self.replace(false);
+                            }
+                        } else {
+                            self.pop_lru(List::T1);
+                        }
+                    } else if total >= self.cap {
+                        if total == 2 * self.cap {
+                            self.pop_lru(List::B2);
+                        }
+                        if self.resident_full() {
+                            self.replace(false);
+                        }
+                    }
+                    self.push(List::T1, Rc::new(RefCell::new(Node::new(key, value))));
+                }
+            }
+        }
+
+        /*   REMOVING A GHOST KEY FORGETS IT, BUT ONLY RESIDENT KEYS HAVE A VALUE TO RETURN   */
+        pub fn remove(&mut self, key: i32) -> Option<i32> {
+            match self.take(key)? {
+                (node, List::T1) | (node, List::T2) => {
+                    let val = node.borrow().val;
+                    Some(val)
+                }
+                _ => None,
+            }
+        }
+
+        pub fn clear_cache(&mut self) {
+            self.keys.clear();
+            self.lists = [Freq::new(0), Freq::new(0), Freq::new(0), Freq::new(0)];
+            self.lens = [0; 4];
+            self.p = 0
+        }
+    }
+
+    impl Cache<i32, i32> for ARCCache {
+        fn get(&mut self, key: &i32) -> Option<i32> {
+            match self.keys.get(key).map(|entry| entry.1) {
+                Some(List::T1) | Some(List::T2) => Some(ARCCache::get(self, *key)),
+                _ => None,
+            }
+        }
+
+        fn put(&mut self, key: i32, value: i32) {
+            ARCCache::put(self, key, value)
+        }
+
+        fn remove(&mut self, key: &i32) -> Option<i32> {
+            ARCCache::remove(self, *key)
+        }
+
+        fn len(&self) -> usize {
+            (self.len_of(List::T1) + self.len_of(List::T2)) as usize
+        }
+
+        fn capacity(&self) -> usize {
+            self.cap.max(0) as usize
+        }
+
+        fn clear(&mut self) {
+            self.clear_cache()
+        }
+    }
+}
+
+/*
+    LEAST RECENTLY/FREQUENTLY USED (LEE ET AL.).
+    EVERY KEY CARRIES A COMBINED RECENCY AND FREQUENCY VALUE (CRF):
+    AN ACCESS AT TIME t ADDS 1 TO IT AND THE WHOLE VALUE DECAYS BY
+    (1/2)^(LAMBDA * ELAPSED). LAMBDA = 0 IS PLAIN LFU, LAMBDA >= 1 IS LRU.
+
+    DECAY IS THE SAME FOR ALL KEYS, SO THEIR ORDER ONLY CHANGES WHEN ONE
+    OF THEM IS ACCESSED. THE HEAP THEREFORE COMPARES LOG2(CRF) + LAMBDA * LAST,
+    WHICH IS THE CRF SCALED TO A COMMON POINT IN TIME AND NEVER OVERFLOWS.
+
+    UNLIKE THE OTHER SINGLE-THREADED POLICIES THIS ONE NEEDS `std`: THE
+    PRIORITIES USE `f64::log2` AND `exp2`, WHICH `core` DOES NOT HAVE
+*/
+#[cfg(feature = "std")]
+#[allow(non_snake_case)]
+pub mod LRFU {
+    use super::Cache;
+    use crate::table::HashMap;
+    use alloc::vec::Vec;
+    use core::fmt;
+
+    #[derive(Debug)]
+    struct Entry {
+        val: i32,
+        crf: f64,
+        last: u64,
+        pos: usize,
+    }
+
+    #[derive(Debug)]
+    pub struct LRFUCache {
+        cap: i32,
+        lambda: f64,
+        time: u64,
+        keys: HashMap<i32, Entry>,
+        heap: Vec<i32>,
+    }
+
+    impl fmt::Display for LRFUCache {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "length: {}", self.heap.len())?;
+            writeln!(f, "capacity: {}", self.cap)?;
+            write!(f, "lambda: {}", self.lambda)?;
+            if self.heap.is_empty() {
+                return write!(f, "\n[empty]");
+            }
+            let mut order = self.heap.clone();
+            order.sort_by(|a, b| self.priority(*a).partial_cmp(&self.priority(*b)).unwrap());
+            for key in order {
+                let entry = &self.keys[&key];
+                write!(
+                    f,
+                    "\n[ key: {}, value: {}, crf: {:.3} ]",
+                    key,
+                    entry.val,
+                    entry.crf * (-self.lambda * (self.time - entry.last) as f64).exp2()
+                )?;
+            }
+            Ok(())
+        }
+    }
+
+    impl LRFUCache {
+        pub fn new(capacity: i32, lambda: f64) -> Self {
+            LRFUCache {
+                cap: capacity,
+                lambda: lambda.max(0.0),
+                time: 0,
+                keys: HashMap::new(),
+                heap: Vec::new(),
+            }
+        }
+
+        /*   TIES ARE BROKEN BY THE OLDER ACCESS, THE SAME WAY `LFUCache` DOES   */
+        fn priority(&self, key: i32) -> (f64, u64) {
+            let entry = &self.keys[&key];
+            (entry.crf.log2() + self.lambda * entry.last as f64, entry.last)
+        }
+
+        fn less(&self, a: usize, b: usize) -> bool {
+            self.priority(self.heap[a]) < self.priority(self.heap[b])
+        }
+
+        fn swap(&mut self, a: usize, b: usize) {
+            self.heap.swap(a, b);
+            self.keys.get_mut(&self.heap[a]).unwrap().pos = a;
+            self.keys.get_mut(&self.heap[b]).unwrap().pos = b;
+        }
+
+        fn sift_up(&mut self, mut pos: usize) {
+            while pos > 0 {
+                let parent = (pos - 1) / 2;
+                if !self.less(pos, parent) {
+                    break;
+                }
+                self.swap(pos, parent);
+                pos = parent;
+            }
+        }
+
+        fn sift_down(&mut self, mut pos: usize) {
+            loop {
+                let mut smallest = pos;
+                for child in [2 * pos + 1, 2 * pos + 2].iter() {
+                    if *child < self.heap.len() && self.less(*child, smallest) {
+                        smallest = *child;
+                    }
+                }
+                if smallest == pos {
+                    break;
+                }
+                self.swap(pos, smallest);
+                pos = smallest;
+            }
+        }
+
+        /*   AN ACCESS ONLY EVER RAISES THE PRIORITY, SO THE KEY SINKS   */
+        fn touch(&mut self, key: i32) {
+            self.time += 1;
+            let (time, lambda) = (self.time, self.lambda);
+            let entry = self.keys.get_mut(&key).unwrap();
+            entry.crf = 1.0 + entry.crf * (-lambda * (time - entry.last) as f64).exp2();
+            entry.last = time;
+            let pos = entry.pos;
+            self.sift_down(pos);
+        }
+
+        fn invalidate(&mut self) {
+            let last = self.heap.len() - 1;
+            self.swap(0, last);
+            let victim = self.heap.pop().unwrap();
+            self.keys.remove(&victim);
+            self.sift_down(0);
+        }
+
+        pub fn get(&mut self, key: i32) -> i32 {
+            if !self.keys.contains_key(&key) {
+                return -1;
+            }
+            self.touch(key);
+            self.keys[&key].val
+        }
+
+        pub fn put(&mut self, key: i32, value: i32) {
+            if self.cap <= 0 {
+                return;
+            }
+            if let Some(entry) = self.keys.get_mut(&key) {
+                entry.val = value;
+                self.touch(key);
+                return;
+            }
+            if self.heap.len() as i32 >= self.cap {
+                self.invalidate();
+            }
+            self.time += 1;
+            self.keys.insert(
+                key,
+                Entry {
+                    val: value,
+                    crf: 1.0,
+                    last: self.time,
+                    pos: self.heap.len(),
+                },
+            );
+            self.heap.push(key);
+            self.sift_up(self.heap.len() - 1);
+        }
+
+        pub fn remove(&mut self, key: i32) -> Option<i32> {
+            let pos = self.keys.get(&key)?.pos;
+            let last = self.heap.len() - 1;
+            self.swap(pos, last);
+            self.heap.pop();
+            let entry = self.keys.remove(&key).unwrap();
+            if pos < self.heap.len() {
+                self.sift_up(pos);
+                self.sift_down(pos);
+            }
+            Some(entry.val)
+        }
+
+        pub fn clear_cache(&mut self) {
+            self.keys.clear();
+            self.heap.clear();
+            self.time = 0
+        }
+    }
+
+    impl Cache<i32, i32> for LRFUCache {
+        fn get(&mut self, key: &i32) -> Option<i32> {
+            if !self.keys.contains_key(key) {
+                return None;
+            }
+            Some(LRFUCache::get(self, *key))
+        }
+
+        fn put(&mut self, key: i32, value: i32) {
+            LRFUCache::put(self, key, value)
+        }
+
+        fn remove(&mut self, key: &i32) -> Option<i32> {
+            LRFUCache::remove(self, *key)
+        }
+
+        fn len(&self) -> usize {
+            self.heap.len()
+        }
+
+        fn capacity(&self) -> usize {
+            self.cap.max(0) as usize
+        }
+
+        fn clear(&mut self) {
+            self.clear_cache()
+        }
+    }
+}
+
+/*
+    GREEDYDUAL-SIZE-FREQUENCY (CHERKASOVA).
+    CAPACITY IS A BUDGET OF SIZE UNITS, NOT A NUMBER OF KEYS.
+    EVERY KEY HAS PRIORITY H = L + FREQ * COST / SIZE AND THE LOWEST H IS
+    EVICTED FIRST. L IS THE INFLATION VALUE: IT IS RAISED TO THE PRIORITY OF
+    EVERY VICTIM, SO KEYS THAT ARE NOT ACCESSED AGAIN AGE OUT OVER TIME.
+    THE VICTIM ORDER IS NOT THE FREQUENCY ORDER: KEYS WITH THE SAME FREQUENCY
+    HAVE DIFFERENT PRIORITIES THROUGH COST, SIZE AND THE L THEY WERE LAST
+    ACCESSED UNDER, SO THE `LFU` BUCKET CHAIN CANNOT HOLD THEM AND THEY ARE
+    KEPT IN A SET ORDERED BY PRIORITY INSTEAD
+*/
+#[allow(non_snake_case)]
+pub mod GDSF {
+    use super::Cache;
+    use crate::table::HashMap;
+    use alloc::collections::BTreeSet;
+    use core::fmt;
+
+    #[derive(Debug)]
+    struct Entry {
+        val: i32,
+        freq: i32,
+        cost: f64,
+        size: i32,
+        priority: f64,
+        seq: u64,
+    }
+
+    /*
+        PRIORITIES ARE NEVER NEGATIVE, AND FOR NON NEGATIVE FLOATS THE ORDER
+        OF THEIR BIT PATTERNS IS THE ORDER OF THEIR VALUES. SEQ BREAKS TIES
+        IN FAVOUR OF EVICTING THE OLDER ACCESS
+    */
+    fn rank(key: i32, entry: &Entry) -> (u64, u64, i32) {
+        (entry.priority.to_bits(), entry.seq, key)
+    }
+
+    #[derive(Debug)]
+    pub struct GDSFCache {
+        cap: i32,
+        used: i32,
+        inflation: f64,
+        seq: u64,
+        keys: HashMap<i32, Entry>,
+        queue: BTreeSet<(u64, u64, i32)>,
+    }
+
+    impl fmt::Display for GDSFCache {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "length: {}", self.keys.len())?;
+            writeln!(f, "capacity: {} ({} used)", self.cap, self.used)?;
+            write!(f, "inflation: {:.3}", self.inflation)?;
+            if self.keys.is_empty() {
+                return write!(f, "\n[empty]");
+            }
+            for (_, _, key) in self.queue.iter() {
+                let entry = &self.keys[key];
+                write!(
+                    f,
+                    "\n[ key: {}, value: {}, size: {}, priority: {:.3} ]",
+                    key, entry.val, entry.size, entry.priority
+                )?;
+            }
+            Ok(())
+        }
+    }
+
+    impl GDSFCache {
+        pub fn new(capacity: i32) -> Self {
+            GDSFCache {
+                cap: capacity,
+                used: 0,
+                inflation: 0.0,
+                seq: 0,
+                keys: HashMap::new(),
+                queue: BTreeSet::new(),
+            }
+        }
+
+        fn reprioritize(&mut self, key: i32) {
+            self.seq += 1;
+            let entry = self.keys.get_mut(&key).unwrap();
+            self.queue.remove(&rank(key, entry));
+            entry.priority = self.inflation + entry.freq as f64 * entry.cost / entry.size as f64;
+            entry.seq = self.seq;
+            self.queue.insert(rank(key, entry));
+        }
+
+        fn invalidate(&mut self) {
+            let victim = *self.queue.iter().next().unwrap();
+            self.queue.remove(&victim);
+            let entry = self.keys.remove(&victim.2).unwrap();
+            self.used -= entry.size;
+            self.inflation = entry.priority;
+        }
+
+        pub fn get(&mut self, key: i32) -> i32 {
+            match self.keys.get_mut(&key) {
+                Some(entry) => {
+                    entry.freq += 1;
+                    let val = entry.val;
+                    self.reprioritize(key);
+                    val
+                }
+                None => -1,
+            }
+        }
+
+        /// Inserts with cost 1 and size 1.
+        pub fn put(&mut self, key: i32, value: i32) {
+            self.put_with(key, value, 1.0, 1)
+        }
+
+        /// Inserts or updates `key`, where `cost` is what fetching it again
+        /// would take and `size` is how much of the capacity it occupies.
+        /// Entries that do not fit into the whole capacity are not stored,
+        /// and an entry already cached under `key` is then left as it was.
+        pub fn put_with(&mut self, key: i32, value: i32, cost: f64, size: i32) {
+            if size <= 0 || size > self.cap {
+                return;
+            }
+            let freq = match self.keys.remove(&key) {
+                Some(entry) => {
+                    self.queue.remove(&rank(key, &entry));
+                    self.used -= entry.size;
+                    entry.freq + 1
+                }
+                None => 1,
+            };
+            while self.used + size > self.cap {
+                self.invalidate();
+            }
+            self.used += size;
+            self.keys.insert(
+                key,
+                Entry {
+                    val: value,
+                    freq,
+                    cost: cost.max(0.0),
+                    size,
+                    priority: 0.0,
+                    seq: 0,
+                },
+            );
+            self.queue.insert(rank(key, &self.keys[&key]));
+            self.reprioritize(key);
+        }
+
+        pub fn remove(&mut self, key: i32) -> Option<i32> {
+            let entry = self.keys.remove(&key)?;
+            self.queue.remove(&rank(key, &entry));
+            self.used -= entry.size;
+            Some(entry.val)
+        }
+
+        pub fn clear_cache(&mut self) {
+            self.keys.clear();
+            self.queue.clear();
+            self.used = 0;
+            self.inflation = 0.0
+        }
+    }
+
+    /*   ENTRIES PUT THROUGH THE TRAIT HAVE COST 1 AND SIZE 1   */
+    impl Cache<i32, i32> for GDSFCache {
+        fn get(&mut self, key: &i32) -> Option<i32> {
+            if !self.keys.contains_key(key) {
+                return None;
+            }
+            Some(GDSFCache::get(self, *key))
+        }
+
+        fn put(&mut self, key: i32, value: i32) {
+            GDSFCache::put(self, key, value)
+        }
+
+        fn remove(&mut self, key: &i32) -> Option<i32> {
+            GDSFCache::remove(self, *key)
+        }
+
+        fn len(&self) -> usize {
+            self.keys.len()
+        }
+
+        fn capacity(&self) -> usize {
+            self.cap.max(0) as usize
+        }
+
+        fn clear(&mut self) {
+            self.clear_cache()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::GDSFCache;
+
+        fn priority(cache: &GDSFCache, key: i32) -> f64 {
+            cache.keys[&key].priority
+        }
+
+        #[test]
+        fn priority_is_inflation_plus_frequency_times_cost_per_size() {
+            let mut cache = GDSFCache::new(10);
+            cache.put_with(1, 1, 6.0, 3);
+            assert_eq!(priority(&cache, 1), 2.0);
+            cache.get(1);
+            assert_eq!(priority(&cache, 1), 4.0);
+            cache.put_with(1, 1, 6.0, 4);
+            assert_eq!(priority(&cache, 1), 4.5);
+            cache.put_with(2, 2, 0.0, 1);
+            assert_eq!(priority(&cache, 2), 0.0);
+        }
+
+        #[test]
+        fn inflation_rises_to_every_victims_priority() {
+            let mut cache = GDSFCache::new(4);
+            cache.put_with(1, 1, 6.0, 3);
+            cache.put_with(2, 2, 1.0, 1);
+            assert_eq!(cache.inflation, 0.0);
+
+            /*   2 HAS THE LOWEST PRIORITY, 1.0, SO L BECOMES 1.0 AND 3 STARTS FROM THERE   */
+            cache.put_with(3, 3, 3.0, 1);
+            assert_eq!(cache.get(2), -1);
+            assert_eq!(cache.inflation, 1.0);
+            assert_eq!(priority(&cache, 3), 4.0);
+
+            /*   THEN 1 AT 2.0, AND 4 AT 3.0 ONCE A READ HAS LIFTED 3 TO 8.0   */
+            cache.put_with(4, 4, 2.0, 2);
+            assert_eq!(cache.get(1), -1);
+            assert_eq!(cache.inflation, 2.0);
+            assert_eq!(priority(&cache, 4), 3.0);
+            cache.get(3);
+            assert_eq!(priority(&cache, 3), 8.0);
+            cache.put_with(5, 5, 1.0, 2);
+            assert_eq!(cache.get(4), -1);
+            assert_eq!(cache.inflation, 3.0);
+            assert_eq!(priority(&cache, 5), 3.5);
+        }
+    }
+}
+
+/*
+    LEAST RECENTLY USED. ONE `Freq` BUCKET USED AS A PLAIN LIST:
+    HEAD IS THE NEXT VICTIM, EVERY HIT MOVES THE NODE TO THE TAIL
+*/
+#[allow(non_snake_case)]
+pub mod LRU {
+    use super::Cache;
+    use super::LFU::{Freq, Node};
+    use crate::table::HashMap;
+    use alloc::rc::Rc;
+    use core::cell::RefCell;
+    use core::fmt;
+
+    #[derive(Debug)]
+    pub struct LRUCache {
+        cap: i32,
+        keys: HashMap<i32, Rc<RefCell<Node>>>,
+        list: Freq,
+    }
+
+    impl fmt::Display for LRUCache {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            super::display_list(f, self.keys.len(), self.cap, &self.list)
+        }
+    }
+
+    impl LRUCache {
+        pub fn new(capacity: i32) -> Self {
+            LRUCache {
+                cap: capacity,
+                keys: HashMap::new(),
+                list: Freq::new(0),
+            }
+        }
+
+        pub fn get(&mut self, key: i32) -> i32 {
+            match self.keys.get(&key) {
+                Some(node) => {
+                    self.list.unlink(node);
+                    self.list.push_back(node.clone());
+                    let val = node.borrow().val;
+                    val
+                }
+                None => -1,
+            }
+        }
+
+        pub fn put(&mut self, key: i32, value: i32) {
+            if self.cap <= 0 {
+                return;
+            }
+            if let Some(node) = self.keys.get(&key) {
+                node.borrow_mut().val = value;
+                self.list.unlink(node);
+                self.list.push_back(node.clone());
+                return;
+            }
+            if self.keys.len() as i32 >= self.cap {
+                let victim = self.list.pop_front().unwrap();
+                self.keys.remove(&victim.borrow().key);
+            }
+            let node = Rc::new(RefCell::new(Node::new(key, value)));
+            self.list.push_back(node.clone());
+            self.keys.insert(key, node);
+        }
+
+        pub fn remove(&mut self, key: i32) -> Option<i32> {
+            let node = self.keys.remove(&key)?;
+            self.list.unlink(&node);
+            let val = node.borrow().val;
+            Some(val)
+        }
+
+        pub fn clear_cache(&mut self) {
+            self.keys.clear();
+            self.list = Freq::new(0)
+        }
+    }
+
+    impl Cache<i32, i32> for LRUCache {
+        fn get(&mut self, key: &i32) -> Option<i32> {
+            if !self.keys.contains_key(key) {
+                return None;
+            }
+            Some(LRUCache::get(self, *key))
+        }
+
+        fn put(&mut self, key: i32, value: i32) {
+            LRUCache::put(self, key, value)
+        }
+
+        fn remove(&mut self, key: &i32) -> Option<i32> {
+            LRUCache::remove(self, *key)
+        }
+
+        fn len(&self) -> usize {
+            self.keys.len()
+        }
+
+        fn capacity(&self) -> usize {
+            self.cap.max(0) as usize
+        }
+
+        fn clear(&mut self) {
+            self.clear_cache()
+        }
+    }
+}
+
+/*
+    FIRST IN, FIRST OUT. THE SAME LIST AS `LRU`, BUT HITS DO NOT MOVE NODES
+*/
+#[allow(non_snake_case)]
+pub mod FIFO {
+    use super::Cache;
+    use super::LFU::{Freq, Node};
+    use crate::table::HashMap;
+    use alloc::rc::Rc;
+    use core::cell::RefCell;
+    use core::fmt;
+
+    #[derive(Debug)]
+    pub struct FIFOCache {
+        cap: i32,
+        keys: HashMap<i32, Rc<RefCell<Node>>>,
+        list: Freq,
+    }
+
+    impl fmt::Display for FIFOCache {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            super::display_list(f, self.keys.len(), self.cap, &self.list)
+        }
+    }
+
+    impl FIFOCache {
+        pub fn new(capacity: i32) -> Self {
+            FIFOCache {
+                cap: capacity,
+                keys: HashMap::new(),
+                list: Freq::new(0),
+            }
+        }
+
+        pub fn get(&mut self, key: i32) -> i32 {
+            match self.keys.get(&key) {
+                Some(node) => node.borrow().val,
+                None => -1,
+            }
+        }
+
+        pub fn put(&mut self, key: i32, value: i32) {
+            if self.cap <= 0 {
+                return;
+            }
+            if let Some(node) = self.keys.get(&key) {
+                node.borrow_mut().val = value;
+                return;
+            }
+            if self.keys.len() as i32 >= self.cap {
+                let victim = self.list.pop_front().unwrap();
+                self.keys.remove(&victim.borrow().key);
+            }
+            let node = Rc::new(RefCell::new(Node::new(key, value)));
+            self.list.push_back(node.clone());
+            self.keys.insert(key, node);
+        }
+
+        pub fn remove(&mut self, key: i32) -> Option<i32> {
+            let node = self.keys.remove(&key)?;
+            self.list.unlink(&node);
+            let val = node.borrow().val;
+            Some(val)
+        }
+
+        pub fn clear_cache(&mut self) {
+            self.keys.clear();
+            self.list = Freq::new(0)
+        }
+    }
+
+    impl Cache<i32, i32> for FIFOCache {
+        fn get(&mut self, key: &i32) -> Option<i32> {
+            if !self.keys.contains_key(key) {
+                return None;
+            }
+            Some(FIFOCache::get(self, *key))
+        }
+
+        fn put(&mut self, key: i32, value: i32) {
+            FIFOCache::put(self, key, value)
+        }
+
+        fn remove(&mut self, key: &i32) -> Option<i32> {
+            FIFOCache::remove(self, *key)
+        }
+
+        fn len(&self) -> usize {
+            self.keys.len()
+        }
+
+        fn capacity(&self) -> usize {
+            self.cap.max(0) as usize
+        }
+
+        fn clear(&mut self) {
+            self.clear_cache()
+        }
+    }
+}
+
+/*
+    CLOCK (SECOND CHANCE). SLOTS FORM A RING SWEPT BY `hand`.
+    A HIT ONLY SETS THE REFERENCED BIT; ON EVICTION THE HAND CLEARS
+    REFERENCED BITS UNTIL IT FINDS A SLOT WITHOUT ONE AND TAKES IT.
+    SLOTS FREED BY `remove` ARE REUSED BEFORE THE RING GROWS
+*/
+#[allow(non_snake_case)]
+pub mod CLOCK {
+    use super::Cache;
+    use crate::table::HashMap;
+    use alloc::vec::Vec;
+    use core::fmt;
+
+    #[derive(Debug)]
+    struct Slot {
+        key: i32,
+        val: i32,
+        referenced: bool,
+    }
+
+    #[derive(Debug)]
+    pub struct CLOCKCache {
+        cap: i32,
+        hand: usize,
+        keys: HashMap<i32, usize>,
+        slots: Vec<Option<Slot>>,
+        free: Vec<usize>,
+    }
+
+    impl fmt::Display for CLOCKCache {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "length: {}", self.keys.len())?;
+            write!(f, "capacity: {}", self.cap)?;
+            if self.keys.is_empty() {
+                return write!(f, "\n[empty]");
+            }
+            for (i, slot) in self.slots.iter().enumerate() {
+                if let Some(slot) = slot {
+                    write!(
+                        f,
+                        "\n{} [ key: {}, value: {}, referenced: {} ]",
+                        if i == self.hand { ">" } else { " " },
+                        slot.key,
+                        slot.val,
+                        slot.referenced
+                    )?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl CLOCKCache {
+        pub fn new(capacity: i32) -> Self {
+            CLOCKCache {
+                cap: capacity,
+                hand: 0,
+                keys: HashMap::new(),
+                slots: Vec::new(),
+                free: Vec::new(),
+            }
+        }
+
+        pub fn get(&mut self, key: i32) -> i32 {
+            match self.keys.get(&key) {
+                Some(&i) => {
+                    let slot = self.slots[i].as_mut().unwrap();
+                    slot.referenced = true;
+                    slot.val
+                }
+                None => -1,
+            }
+        }
+
+        /*   THE CACHE IS FULL HERE, SO EVERY SLOT IS OCCUPIED   */
+        fn invalidate(&mut self) -> usize {
+            loop {
+                let slot = self.slots[self.hand].as_mut().unwrap();
+                if !slot.referenced {
+                    let victim = self.hand;
+                    self.keys.remove(&slot.key);
+                    self.hand = (self.hand + 1) % self.slots.len();
+                    return victim;
+                }
+                slot.referenced = false;
+                self.hand = (self.hand + 1) % self.slots.len();
+            }
+        }
+
+        pub fn put(&mut self, key: i32, value: i32) {
+            if self.cap <= 0 {
+                return;
+            }
+            if let Some(&i) = self.keys.get(&key) {
+                let slot = self.slots[i].as_mut().unwrap();
+                slot.val = value;
+                slot.referenced = true;
+                return;
+            }
+            let slot = Some(Slot {
+                key,
+                val: value,
+                referenced: false,
+            });
+            let i = if self.keys.len() as i32 >= self.cap {
+                self.invalidate()
+            } else if let Some(i) = self.free.pop() {
+                i
+            } else {
+                self.slots.push(None);
+                self.slots.len() - 1
+            };
+            self.slots[i] = slot;
+            self.keys.insert(key, i);
+        }
+
+        pub fn remove(&mut self, key: i32) -> Option<i32> {
+            let i = self.keys.remove(&key)?;
+            self.free.push(i);
+            self.slots[i].take().map(|slot| slot.val)
+        }
+
+        pub fn clear_cache(&mut self) {
+            self.keys.clear();
+            self.slots.clear();
+            self.free.clear();
+            self.hand = 0
+        }
+    }
+
+    impl Cache<i32, i32> for CLOCKCache {
+        fn get(&mut self, key: &i32) -> Option<i32> {
+            if !self.keys.contains_key(key) {
+                return None;
+            }
+            Some(CLOCKCache::get(self, *key))
+        }
+
+        fn put(&mut self, key: i32, value: i32) {
+            CLOCKCache::put(self, key, value)
+        }
+
+        fn remove(&mut self, key: &i32) -> Option<i32> {
+            CLOCKCache::remove(self, *key)
+        }
+
+        fn len(&self) -> usize {
+            self.keys.len()
+        }
+
+        fn capacity(&self) -> usize {
+            self.cap.max(0) as usize
+        }
+
+        fn clear(&mut self) {
+            self.clear_cache()
+        }
+    }
+}
+
+/*
+    SEGMENTED LFU. NEW KEYS START IN THE PROBATION SEGMENT. ONCE A KEY'S
+    FREQUENCY REACHES THE PROMOTION THRESHOLD IT MOVES TO THE PROTECTED
+    SEGMENT, WHICH HAS ITS OWN SHARE OF THE CAPACITY. WHEN THE PROTECTED
+    SEGMENT IS FULL ITS LFU VICTIM IS DEMOTED BACK TO PROBATION WITH THE
+    FREQUENCY IT HAD. EVICTION ONLY EVER TAKES THE PROBATION LFU VICTIM
+*/
+#[allow(non_snake_case)]
+pub mod SLFU {
+    use super::Cache;
+    use super::LFU::LFUCache;
+    use core::fmt;
+
+    #[derive(Debug)]
+    pub struct SLFUCache {
+        cap: i32,
+        protected_cap: i32,
+        threshold: i32,
+        probation: LFUCache,
+        protected: LFUCache,
+    }
+
+    impl fmt::Display for SLFUCache {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "promotion threshold: {}", self.threshold)?;
+            writeln!(f, "protected")?;
+            writeln!(f, "{}", self.protected)?;
+            writeln!(f, "probation")?;
+            write!(f, "{}", self.probation)
+        }
+    }
+
+    impl SLFUCache {
+        /// `protected_capacity` of the `capacity` slots are reserved for keys
+        /// that were accessed at least `threshold` times. At least one slot is
+        /// always left to probation so that new keys can get in. A key keeps
+        /// its frequency when it is promoted or demoted, and linking it into
+        /// the other segment costs O(number of distinct frequencies) there.
+        pub fn new(capacity: i32, protected_capacity: i32, threshold: i32) -> Self {
+            let protected_cap = protected_capacity.min(capacity - 1).max(0);
+            SLFUCache {
+                cap: capacity,
+                protected_cap,
+                threshold: threshold.max(2),
+                probation: LFUCache::new(capacity),
+                protected: LFUCache::new(protected_cap),
+            }
+        }
+
+        fn len(&self) -> i32 {
+            Cache::len(&self.probation) as i32 + Cache::len(&self.protected) as i32
+        }
+
+        /*   MOVE A PROBATION KEY THAT REACHED THE THRESHOLD TO PROTECTED   */
+        fn promote(&mut self, key: i32) {
+            if self.protected_cap == 0 || self.probation.freq_of(key).unwrap() < self.threshold {
+                return;
+            }
+            let (val, freq) = self.probation.take(key).unwrap();
+            if Cache::len(&self.protected) as i32 == self.protected_cap {
+                let (demoted, demoted_val, demoted_freq) = self.protected.pop_victim().unwrap();
+                self.probation
+                    .insert_with_freq(demoted, demoted_val, demoted_freq);
+            }
+            self.protected.insert_with_freq(key, val, freq);
+        }
+
+        pub fn get(&mut self, key: i32) -> i32 {
+            if self.protected.contains(key) {
+                return self.protected.get(key);
+            }
+            if !self.probation.contains(key) {
+                return -1;
+            }
+            let val = self.probation.get(key);
+            self.promote(key);
+            val
+        }
+
+        pub fn put(&mut self, key: i32, value: i32) {
+            if self.cap <= 0 {
+                return;
+            }
+            if self.protected.contains(key) {
+                return self.protected.put(key, value);
+            }
+            if self.probation.contains(key) {
+                self.probation.put(key, value);
+                return self.promote(key);
+            }
+            if self.len() == self.cap {
+                self.probation.pop_victim();
+            }
+            self.probation.insert_with_freq(key, value, 1);
+        }
+
+        pub fn remove(&mut self, key: i32) -> Option<i32> {
+            self.protected
+                .remove(key)
+                .or_else(|| self.probation.remove(key))
+        }
+
+        pub fn clear_cache(&mut self) {
+            self.probation.clear_cache();
+            self.protected.clear_cache()
+        }
+    }
+
+    impl Cache<i32, i32> for SLFUCache {
+        fn get(&mut self, key: &i32) -> Option<i32> {
+            if !self.protected.contains(*key) && !self.probation.contains(*key) {
+                return None;
+            }
+            Some(SLFUCache::get(self, *key))
+        }
+
+        fn put(&mut self, key: i32, value: i32) {
+            SLFUCache::put(self, key, value)
+        }
+
+        fn remove(&mut self, key: &i32) -> Option<i32> {
+            SLFUCache::remove(self, *key)
+        }
+
+        fn len(&self) -> usize {
+            SLFUCache::len(self) as usize
+        }
+
+        fn capacity(&self) -> usize {
+            self.cap.max(0) as usize
+        }
+
+        fn clear(&mut self) {
+            self.clear_cache()
+        }
+    }
+}
+
+/*
+    ONE LFU CACHE SHARED BY SEVERAL NAMESPACES (TENANTS). THE ENTRIES SHARE ONE
+    CAPACITY, BUT THEY ARE NOT NAMESPACE-TAGGED KEYS IN A SINGLE FREQUENCY
+    CHAIN: EVERY NAMESPACE KEEPS ITS OWN `LFUCache`, SO ITS LEAST FREQUENT KEY
+    IS THE HEAD OF ITS OWN CHAIN INSTEAD OF SOMEWHERE BEHIND OTHER TENANTS' KEYS
+    THAT ARE STILL WITHIN THEIR GUARANTEE.
+    EVERY NAMESPACE HAS A QUOTA: `min` ENTRIES IT IS GUARANTEED TO KEEP AND
+    `max` ENTRIES IT MAY NEVER GROW BEYOND.
+
+    A NAMESPACE AT ITS `max` EVICTS ITS OWN LFU VICTIM. OTHERWISE, WHEN THE
+    CACHE IS FULL, THE VICTIM IS THE LOWEST FREQUENCY ONE AMONG THE NAMESPACES
+    OVER QUOTA, MEANING HOLDING MORE THAN THEIR `min`, SO A NOISY TENANT CAN ONLY
+    PUSH OTHERS DOWN TO THEIR GUARANTEE. THE PRICE OF SEPARATE CHAINS IS THAT
+    FINDING THAT VICTIM COMPARES EVERY NAMESPACE'S HEAD: EVICTION IS
+    O(NUMBER OF NAMESPACES), NOT O(1).
+
+    IF THE `min`S ADD UP TO MORE THAN THE CAPACITY, NOBODY MAY BE OVER QUOTA
+    WHEN THE CACHE FILLS. A NEW KEY THEN REPLACES ITS OWN NAMESPACE'S VICTIM,
+    OR IS NOT CACHED IF ITS NAMESPACE HOLDS NOTHING, SO THE CAPACITY STILL HOLDS
+*/
+#[allow(non_snake_case)]
+pub mod NAMESPACE {
+    use super::Cache;
+    use super::LFU::LFUCache;
+    use alloc::collections::BTreeMap;
+    use core::fmt;
+
+    #[derive(Debug)]
+    struct Namespace {
+        min: i32,
+        max: i32,
+        cache: LFUCache,
+    }
+
+    impl Namespace {
+        fn len(&self) -> i32 {
+            Cache::len(&self.cache) as i32
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct NamespacedLFUCache {
+        cap: i32,
+        len: i32,
+        spaces: BTreeMap<i32, Namespace>,
+    }
+
+    impl fmt::Display for NamespacedLFUCache {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "length: {}", self.len)?;
+            write!(f, "capacity: {}", self.cap)?;
+            for (ns, space) in self.spaces.iter() {
+                write!(
+                    f,
+                    "\nnamespace {} (min {}, max {}) : {} entries",
+                    ns,
+                    space.min,
+                    space.max,
+                    space.len()
+                )?;
+            }
+            Ok(())
+        }
+    }
+
+    impl NamespacedLFUCache {
+        pub fn new(capacity: i32) -> Self {
+            NamespacedLFUCache {
+                cap: capacity,
+                len: 0,
+                spaces: BTreeMap::new(),
+            }
+        }
+
+        /// Guarantees `namespace` at least `min` entries and caps it at `max`.
+        /// Namespaces without a quota have min 0 and max equal to the capacity.
+        /// Guarantees only hold while the sum of all `min`s fits the capacity.
+        pub fn set_quota(&mut self, namespace: i32, min: i32, max: i32) {
+            let space = self.space(namespace);
+            space.min = min.max(0);
+            space.max = max.max(space.min);
+            let mut evicted = 0;
+            while space.len() > space.max {
+                space.cache.pop_victim();
+                evicted += 1;
+            }
+            self.len -= evicted;
+        }
+
+        fn space(&mut self, namespace: i32) -> &mut Namespace {
+            let cap = self.cap;
+            self.spaces.entry(namespace).or_insert_with(|| Namespace {
+                min: 0,
+                max: cap,
+                cache: LFUCache::new(cap),
+            })
+        }
+
+        /*   THE NAMESPACE HOLDING THE LFU VICTIM AMONG THOSE OVER THEIR GUARANTEE   */
+        fn victim_namespace(&self) -> Option<i32> {
+            self.spaces
+                .iter()
+                .filter(|(_, space)| space.len() > space.min)
+                .filter_map(|(ns, space)| {
+                    space
+                        .cache
+                        .victim_freq()
+                        .map(|freq| (freq, space.min - space.len(), *ns))
+                })
+                .min()
+                .map(|(_, _, ns)| ns)
+        }
+
+        pub fn get(&mut self, namespace: i32, key: i32) -> i32 {
+            match self.spaces.get_mut(&namespace) {
+                Some(space) => space.cache.get(key),
+                None => -1,
+            }
+        }
+
+        pub fn put(&mut self, namespace: i32, key: i32, value: i32) {
+            if self.cap <= 0 {
+                return;
+            }
+            let space = self.space(namespace);
+            if space.cache.contains(key) {
+                return space.cache.put(key, value);
+            }
+            if space.max == 0 {
+                return;
+            }
+            if space.len() >= space.max {
+                space.cache.pop_victim();
+                self.len -= 1;
+            } else if self.len >= self.cap {
+                /*   IF EVERYONE IS WITHIN THEIR GUARANTEE THE NEW KEY CAN ONLY REPLACE ONE OF ITS OWN   */
+                let victim = match self.victim_namespace() {
+                    Some(victim) => victim,
+                    None if self.len_of(namespace) > 0 => namespace,
+                    None => return,
+                };
+                self.spaces.get_mut(&victim).unwrap().cache.pop_victim();
+                self.len -= 1;
+            }
+            self.space(namespace).cache.insert_with_freq(key, value, 1);
+            self.len += 1;
+        }
+
+        pub fn remove(&mut self, namespace: i32, key: i32) -> Option<i32> {
+            let val = self.spaces.get_mut(&namespace)?.cache.remove(key)?;
+            self.len -= 1;
+            Some(val)
+        }
+
+        /*   QUOTAS SURVIVE A CLEAR, ONLY THE ENTRIES GO   */
+        pub fn clear_cache(&mut self) {
+            for space in self.spaces.values_mut() {
+                space.cache.clear_cache();
+            }
+            self.len = 0
+        }
+
+        pub fn len_of(&self, namespace: i32) -> i32 {
+            self.spaces.get(&namespace).map_or(0, |space| space.len())
+        }
+    }
+
+    /*   KEYS ARE (NAMESPACE, KEY) PAIRS   */
+    impl Cache<(i32, i32), i32> for NamespacedLFUCache {
+        fn get(&mut self, key: &(i32, i32)) -> Option<i32> {
+            let space = self.spaces.get_mut(&key.0)?;
+            if !space.cache.contains(key.1) {
+                return None;
+            }
+            Some(space.cache.get(key.1))
+        }
+
+        fn put(&mut self, key: (i32, i32), value: i32) {
+            NamespacedLFUCache::put(self, key.0, key.1, value)
+        }
+
+        fn remove(&mut self, key: &(i32, i32)) -> Option<i32> {
+            NamespacedLFUCache::remove(self, key.0, key.1)
+        }
+
+        fn len(&self) -> usize {
+            self.len as usize
+        }
+
+        fn capacity(&self) -> usize {
+            self.cap.max(0) as usize
+        }
+
+        fn clear(&mut self) {
+            self.clear_cache()
+        }
+    }
+}
+
+/*
+    APPROXIMATE LFU BY SAMPLING, THE WAY REDIS DOES IT. ENTRIES ARE KEPT IN
+    A FLAT VECTOR WITH NOTHING BUT KEY, VALUE AND COUNTER, AND EVICTION
+    REMOVES THE LOWEST COUNTER AMONG `samples` RANDOMLY CHOSEN ENTRIES.
+    THE VICTIM IS NOT ALWAYS THE GLOBAL LFU ONE, BUT THERE ARE NO LINKS
+    TO MAINTAIN AND EVERY OPERATION IS O(samples)
+*/
+#[allow(non_snake_case)]
+pub mod SAMPLED {
+    use super::Cache;
+    use crate::rng::XorShift;
+    use crate::table::HashMap;
+    use alloc::vec::Vec;
+    use core::fmt;
+
+    const DEFAULT_SAMPLES: usize = 5;
+
+    #[derive(Debug)]
+    struct Entry {
+        key: i32,
+        val: i32,
+        freq: i32,
+    }
+
+    #[derive(Debug)]
+    pub struct SampledLFUCache {
+        cap: i32,
+        samples: usize,
+        rng: XorShift,
+        index: HashMap<i32, usize>,
+        entries: Vec<Entry>,
+    }
+
+    impl fmt::Display for SampledLFUCache {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "length: {}", self.entries.len())?;
+            writeln!(f, "capacity: {}", self.cap)?;
+            write!(f, "samples: {}", self.samples)?;
+            if self.entries.is_empty() {
+                return write!(f, "\n[empty]");
+            }
+            for entry in self.entries.iter() {
+                write!(
+                    f,
+                    "\n[ key: {}, value: {}, frequency: {} ]",
+                    entry.key, entry.val, entry.freq
+                )?;
+            }
+            Ok(())
+        }
+    }
+
+    impl SampledLFUCache {
+        pub fn new(capacity: i32) -> Self {
+            SampledLFUCache::with_samples(capacity, DEFAULT_SAMPLES)
+        }
+
+        /// More samples make eviction closer to exact LFU and slower.
+        pub fn with_samples(capacity: i32, samples: usize) -> Self {
+            SampledLFUCache {
+                cap: capacity,
+                samples: samples.max(1),
+                rng: XorShift::new(0),
+                index: HashMap::new(),
+                entries: Vec::new(),
+            }
+        }
+
+        /*   SWAP THE LAST ENTRY INTO THE HOLE SO THE VECTOR STAYS DENSE   */
+        fn remove_at(&mut self, i: usize) -> Entry {
+            let entry = self.entries.swap_remove(i);
+            self.index.remove(&entry.key);
+            if let Some(moved) = self.entries.get(i) {
+                self.index.insert(moved.key, i);
+            }
+            entry
+        }
+
+        fn invalidate(&mut self) {
+            let len = self.entries.len() as u64;
+            let mut victim = self.rng.below(len) as usize;
+            for _ in 1..self.samples {
+                let i = self.rng.below(len) as usize;
+                if self.entries[i].freq < self.entries[victim].freq {
+                    victim = i;
+                }
+            }
+            self.remove_at(victim);
+        }
+
+        pub fn get(&mut self, key: i32) -> i32 {
+            match self.index.get(&key) {
+                Some(&i) => {
+                    let entry = &mut self.entries[i];
+                    entry.freq = entry.freq.saturating_add(1);
+                    entry.val
+                }
+                None => -1,
+            }
+        }
+
+        pub fn put(&mut self, key: i32, value: i32) {
+            if self.cap <= 0 {
+                return;
+            }
+            if let Some(&i) = self.index.get(&key) {
+                let entry = &mut self.entries[i];
+                entry.val = value;
+                entry.freq = entry.freq.saturating_add(1);
+                return;
+            }
+            if self.entries.len() as i32 >= self.cap {
+                self.invalidate();
+            }
+            self.index.insert(key, self.entries.len());
+            self.entries.push(Entry {
+                key,
+                val: value,
+                freq: 1,
+            });
+        }
+
+        pub fn remove(&mut self, key: i32) -> Option<i32> {
+            let i = *self.index.get(&key)?;
+            Some(self.remove_at(i).val)
+        }
+
+        pub fn clear_cache(&mut self) {
+            self.index.clear();
+            self.entries.clear()
+        }
+    }
+
+    impl Cache<i32, i32> for SampledLFUCache {
+        fn get(&mut self, key: &i32) -> Option<i32> {
+            if !self.index.contains_key(key) {
+                return None;
+            }
+            Some(SampledLFUCache::get(self, *key))
+        }
+
+        fn put(&mut self, key: i32, value: i32) {
+            SampledLFUCache::put(self, key, value)
+        }
+
+        fn remove(&mut self, key: &i32) -> Option<i32> {
+            SampledLFUCache::remove(self, *key)
+        }
+
+        fn len(&self) -> usize {
+            self.entries.len()
+        }
+
+        fn capacity(&self) -> usize {
+            self.cap.max(0) as usize
+        }
+
+        fn clear(&mut self) {
+            self.clear_cache()
+        }
+    }
+}
+
+/*
+    THE BUCKET CHAIN OF `SLAB` AND `ARRAY`. NODES AND BUCKETS LIVE IN INDEXED
+    STORAGE AND POINT AT EACH OTHER BY u32 INDEX, WITH `NIL` FOR NO LINK. EACH
+    CACHE SAYS WHERE ITS LINKS AND BUCKETS ARE AND HOW IT RECYCLES BUCKET
+    SLOTS, AND `Chain` DOES THE LINKING FOR BOTH
+*/
+mod indexed {
+    pub(super) const NIL: u32 = u32::MAX;
+
+    #[derive(Debug, Clone, Copy)]
+    pub(super) struct Link {
+        pub(super) prev: u32,
+        pub(super) next: u32,
+        pub(super) parent: u32,
+    }
+
+    pub(super) const UNLINKED: Link = Link {
+        prev: NIL,
+        next: NIL,
+        parent: NIL,
+    };
+
+    #[derive(Debug, Clone, Copy)]
+    pub(super) struct Bucket {
+        pub(super) f: i32,
+        pub(super) head: u32,
+        pub(super) tail: u32,
+        pub(super) prev: u32,
+        pub(super) next: u32,
+    }
+
+    pub(super) const FREE_BUCKET: Bucket = Bucket {
+        f: 0,
+        head: NIL,
+        tail: NIL,
+        prev: NIL,
+        next: NIL,
+    };
+
+    pub(super) trait Chain {
+        fn link(&mut self, n: u32) -> &mut Link;
+
+        fn bucket(&mut self, b: u32) -> &mut Bucket;
+
+        /*   THE LOWEST FREQUENCY BUCKET   */
+        fn head(&mut self) -> &mut u32;
+
+        /*   STORES `bucket` IN A FREE SLOT AND RETURNS THE SLOT   */
+        fn alloc_bucket(&mut self, bucket: Bucket) -> u32;
+
+        fn release_bucket(&mut self, b: u32);
+
+        /*   A NEW BUCKET LINKED RIGHT AFTER `prev`, OR AT THE FRONT IF `prev` IS NIL   */
+        fn new_bucket(&mut self, f: i32, prev: u32) -> u32 {
+            let next = if prev == NIL {
+                *self.head()
+            } else {
+                self.bucket(prev).next
+            };
+            let b = self.alloc_bucket(Bucket {
+                f,
+                head: NIL,
+                tail: NIL,
+                prev,
+                next,
+            });
+            if prev == NIL {
+                *self.head() = b;
+            } else {
+                self.bucket(prev).next = b;
+            }
+            if next != NIL {
+                self.bucket(next).prev = b;
+            }
+            b
+        }
+
+        fn free_bucket(&mut self, b: u32) {
+            let Bucket { prev, next, .. } = *self.bucket(b);
+            if prev == NIL {
+                *self.head() = next;
+            } else {
+                self.bucket(prev).next = next;
+            }
+            if next != NIL {
+                self.bucket(next).prev = prev;
+            }
+            self.release_bucket(b);
+        }
+
+        fn push_back(&mut self, b: u32, n: u32) {
+            let tail = self.bucket(b).tail;
+            *self.link(n) = Link {
+                prev: tail,
+                next: NIL,
+                parent: b,
+            };
+            if tail == NIL {
+                self.bucket(b).head = n;
+            } else {
+                self.link(tail).next = n;
+            }
+            self.bucket(b).tail = n;
+        }
+
+        /*   TAKE A NODE OUT OF ITS BUCKET, DROPPING THE BUCKET IF IT RUNS EMPTY   */
+        fn unlink(&mut self, n: u32) {
+            let Link { prev, next, parent } = *self.link(n);
+            if prev == NIL {
+                self.bucket(parent).head = next;
+            } else {
+                self.link(prev).next = next;
+            }
+            if next == NIL {
+                self.bucket(parent).tail = prev;
+            } else {
+                self.link(next).prev = prev;
+            }
+            if self.bucket(parent).head == NIL {
+                self.free_bucket(parent);
+            }
+        }
+    }
+}
+
+/*
+    THE SAME LFU AS `LFUCache`, BUT WITHOUT `Rc<RefCell<...>>`. NODES AND
+    BUCKETS LIVE IN TWO VECTORS AND POINT AT EACH OTHER BY u32 INDEX, WITH
+    `NIL` FOR NO LINK. SLOTS OF REMOVED NODES AND EMPTIED BUCKETS GO ON FREE
+    LISTS AND ARE REUSED BEFORE THE VECTORS GROW.
+
+    BUCKETS ARE KEPT IN ASCENDING FREQUENCY ORDER, SO THE BUCKET FOR FREQ + 1
+    IS EITHER THE NEXT ONE OR DOES NOT EXIST YET, AND NO FREQUENCY MAP IS NEEDED
+*/
+#[allow(non_snake_case)]
+pub mod SLAB {
+    use super::indexed::{Bucket, Chain, Link, NIL, UNLINKED};
+    use super::Cache;
+    use crate::table::HashMap;
+    use alloc::vec::Vec;
+    use core::fmt;
+
+    #[derive(Debug)]
+    struct Node {
+        key: i32,
+        val: i32,
+        freq: i32,
+        link: Link,
+    }
+
+    #[derive(Debug)]
+    pub struct SlabLFUCache {
+        cap: i32,
+        keys: HashMap<i32, u32>,
+        nodes: Vec<Node>,
+        buckets: Vec<Bucket>,
+        free_nodes: Vec<u32>,
+        free_buckets: Vec<u32>,
+        head: u32,
+    }
+
+    impl fmt::Display for SlabLFUCache {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "length: {}", self.keys.len())?;
+            write!(f, "capacity: {}", self.cap)?;
+            if self.keys.is_empty() {
+                return write!(f, "\n[empty]");
+            }
+            let mut b = self.head;
+            while b != NIL {
+                let bucket = &self.buckets[b as usize];
+                write!(f, "\nfrequency {} : ", bucket.f)?;
+                let mut n = bucket.head;
+                while n != NIL {
+                    let node = &self.nodes[n as usize];
+                    write!(f, "[ key: {}, value: {} ]", node.key, node.val)?;
+                    if node.link.next != NIL {
+                        write!(f, " -> ")?;
+                    }
+                    n = node.link.next;
+                }
+                b = bucket.next;
+            }
+            Ok(())
+        }
+    }
+
+    impl SlabLFUCache {
+        pub fn new(capacity: i32) -> Self {
+            SlabLFUCache {
+                cap: capacity,
+                keys: HashMap::new(),
+                nodes: Vec::new(),
+                buckets: Vec::new(),
+                free_nodes: Vec::new(),
+                free_buckets: Vec::new(),
+                head: NIL,
+            }
+        }
+
+        fn move_node(&mut self, n: u32) {
+            let (b, freq) = {
+                let node = &mut self.nodes[n as usize];
+                node.freq += 1;
+                (node.link.parent, node.freq)
+            };
+            let next = self.buckets[b as usize].next;
+            let target = if next != NIL && self.buckets[next as usize].f == freq {
+                next
+            } else {
+                self.new_bucket(freq, b)
+            };
+            self.unlink(n);
+            self.push_back(target, n);
+        }
+
+        fn invalidate(&mut self) -> (i32, i32) {
+            let n = self.buckets[self.head as usize].head;
+            self.unlink(n);
+            let node = &self.nodes[n as usize];
+            self.keys.remove(&node.key);
+            self.free_nodes.push(n);
+            (node.key, node.val)
+        }
+
+        pub fn get(&mut self, key: i32) -> i32 {
+            match self.keys.get(&key) {
+                Some(&n) => {
+                    self.move_node(n);
+                    self.nodes[n as usize].val
+                }
+                None => -1,
+            }
+        }
+
+        pub fn put(&mut self, key: i32, value: i32) {
+            self.insert(key, value);
+        }
+
+        /*   `put` THAT HANDS BACK THE ENTRY IT HAD TO EVICT, IF ANY   */
+        pub(super) fn insert(&mut self, key: i32, value: i32) -> Option<(i32, i32)> {
+            if self.cap <= 0 {
+                return None;
+            }
+            if let Some(&n) = self.keys.get(&key) {
+                self.nodes[n as usize].val = value;
+                self.move_node(n);
+                return None;
+            }
+            let evicted = if self.keys.len() as i32 >= self.cap {
+                Some(self.invalidate())
+            } else {
+                None
+            };
+            let node = Node {
+                key,
+                val: value,
+                freq: 1,
+                link: UNLINKED,
+            };
+            let n = match self.free_nodes.pop() {
+                Some(n) => {
+                    self.nodes[n as usize] = node;
+                    n
+                }
+                None => {
+                    self.nodes.push(node);
+                    (self.nodes.len() - 1) as u32
+                }
+            };
+            let b = if self.head != NIL && self.buckets[self.head as usize].f == 1 {
+                self.head
+            } else {
+                self.new_bucket(1, NIL)
+            };
+            self.push_back(b, n);
+            self.keys.insert(key, n);
+            evicted
+        }
+
+        pub fn remove(&mut self, key: i32) -> Option<i32> {
+            let n = self.keys.remove(&key)?;
+            self.unlink(n);
+            self.free_nodes.push(n);
+            Some(self.nodes[n as usize].val)
+        }
+
+        pub fn contains(&self, key: i32) -> bool {
+            self.keys.contains_key(&key)
+        }
+
+        /*   ENTRIES FROM THE COLDEST BUCKET UP, OLDEST FIRST INSIDE A BUCKET   */
+        pub fn iter(&self) -> Iter<'_> {
+            let node = if self.head == NIL {
+                NIL
+            } else {
+                self.buckets[self.head as usize].head
+            };
+            Iter { cache: self, node }
+        }
+
+        pub fn clear_cache(&mut self) {
+            self.keys.clear();
+            self.nodes.clear();
+            self.buckets.clear();
+            self.free_nodes.clear();
+            self.free_buckets.clear();
+            self.head = NIL
+        }
+    }
+
+    impl Chain for SlabLFUCache {
+        fn link(&mut self, n: u32) -> &mut Link {
+            &mut self.nodes[n as usize].link
+        }
+
+        fn bucket(&mut self, b: u32) -> &mut Bucket {
+            &mut self.buckets[b as usize]
+        }
+
+        fn head(&mut self) -> &mut u32 {
+            &mut self.head
+        }
+
+        fn alloc_bucket(&mut self, bucket: Bucket) -> u32 {
+            match self.free_buckets.pop() {
+                Some(b) => {
+                    self.buckets[b as usize] = bucket;
+                    b
+                }
+                None => {
+                    self.buckets.push(bucket);
+                    (self.buckets.len() - 1) as u32
+                }
+            }
+        }
+
+        fn release_bucket(&mut self, b: u32) {
+            self.free_buckets.push(b);
+        }
+    }
+
+    pub struct Iter<'a> {
+        cache: &'a SlabLFUCache,
+        node: u32,
+    }
+
+    impl<'a> Iterator for Iter<'a> {
+        type Item = (i32, i32);
+
+        fn next(&mut self) -> Option<(i32, i32)> {
+            if self.node == NIL {
+                return None;
+            }
+            let node = &self.cache.nodes[self.node as usize];
+            self.node = if node.link.next != NIL {
+                node.link.next
+            } else {
+                let next = self.cache.buckets[node.link.parent as usize].next;
+                if next == NIL {
+                    NIL
+                } else {
+                    self.cache.buckets[next as usize].head
+                }
+            };
+            Some((node.key, node.val))
+        }
+    }
+
+    impl Cache<i32, i32> for SlabLFUCache {
+        fn get(&mut self, key: &i32) -> Option<i32> {
+            if !self.keys.contains_key(key) {
+                return None;
+            }
+            Some(SlabLFUCache::get(self, *key))
+        }
+
+        fn put(&mut self, key: i32, value: i32) {
+            SlabLFUCache::put(self, key, value)
+        }
+
+        fn remove(&mut self, key: &i32) -> Option<i32> {
+            SlabLFUCache::remove(self, *key)
+        }
+
+        fn len(&self) -> usize {
+            self.keys.len()
+        }
+
+        fn capacity(&self) -> usize {
+            self.cap.max(0) as usize
+        }
+
+        fn clear(&mut self) {
+            self.clear_cache()
+        }
+    }
+}
+
+/*
+    `LFUCache` IS BUILT ON `Rc<RefCell<...>>` AND CANNOT LEAVE ITS THREAD, SO THE
+    SHARED CACHE WRAPS THE SLAB ONE, WHICH HOLDS PLAIN INDICES, IN A MUTEX
+*/
+#[cfg(feature = "std")]
+#[allow(non_snake_case)]
+pub mod SYNC {
+    use super::unpoisoned;
+    use super::Cache;
+    use super::SLAB::SlabLFUCache;
+    use std::fmt;
+    use std::sync::{Mutex, MutexGuard};
+
+    #[derive(Debug)]
+    pub struct SyncLfuCache {
+        inner: Mutex<SlabLFUCache>,
+    }
+
+    const _: fn() = || {
+        fn shared<T: Send + Sync>() {}
+        shared::<SyncLfuCache>();
+    };
+
+    impl fmt::Display for SyncLfuCache {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", *self.lock())
+        }
+    }
+
+    impl SyncLfuCache {
+        pub fn new(capacity: i32) -> Self {
+            SyncLfuCache {
+                inner: Mutex::new(SlabLFUCache::new(capacity)),
+            }
+        }
+
+        fn lock(&self) -> MutexGuard<'_, SlabLFUCache> {
+            unpoisoned(self.inner.lock())
+        }
+
+        fn lock_mut(&mut self) -> &mut SlabLFUCache {
+            unpoisoned(self.inner.get_mut())
+        }
+
+        pub fn get(&self, key: i32) -> i32 {
+            self.lock().get(key)
+        }
+
+        pub fn put(&self, key: i32, value: i32) {
+            self.lock().put(key, value)
+        }
+
+        pub fn remove(&self, key: i32) -> Option<i32> {
+            self.lock().remove(key)
+        }
+
+        pub fn len(&self) -> usize {
+            Cache::len(&*self.lock())
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        pub fn capacity(&self) -> usize {
+            Cache::capacity(&*self.lock())
+        }
+
+        pub fn clear_cache(&self) {
+            self.lock().clear_cache()
+        }
+    }
+
+    impl Cache<i32, i32> for SyncLfuCache {
+        fn get(&mut self, key: &i32) -> Option<i32> {
+            Cache::get(self.lock_mut(), key)
+        }
+
+        fn put(&mut self, key: i32, value: i32) {
+            SyncLfuCache::put(self, key, value)
+        }
+
+        fn remove(&mut self, key: &i32) -> Option<i32> {
+            SyncLfuCache::remove(self, *key)
+        }
+
+        fn len(&self) -> usize {
+            SyncLfuCache::len(self)
+        }
+
+        fn capacity(&self) -> usize {
+            SyncLfuCache::capacity(self)
+        }
+
+        fn clear(&mut self) {
+            self.clear_cache()
+        }
+    }
+}
+
+/*
+    KEYS ARE SPREAD OVER N SLAB LFU SHARDS BY HASH, EACH BEHIND ITS OWN LOCK, SO
+    HITS ON DIFFERENT SHARDS DO NOT WAIT FOR EACH OTHER. THE CAPACITY IS SPLIT
+    EVENLY, THE FIRST SHARDS TAKING THE REMAINDER, AND EVICTION IS LOCAL TO A
+    SHARD: A KEY ONLY EVER PUSHES OUT A KEY OF ITS OWN SHARD
+*/
+#[cfg(feature = "std")]
+#[allow(non_snake_case)]
+pub mod SHARDED {
+    use super::unpoisoned;
+    use super::Cache;
+    use super::SLAB::SlabLFUCache;
+    use std::collections::hash_map::RandomState;
+    use std::fmt;
+    use std::hash::BuildHasher;
+    use std::sync::{Mutex, MutexGuard};
+    use std::vec::Vec;
+
+    #[derive(Debug)]
+    struct Shard {
+        cache: SlabLFUCache,
+        hits: u64,
+        misses: u64,
+        evictions: u64,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct ShardStats {
+        pub len: usize,
+        pub capacity: usize,
+        pub hits: u64,
+        pub misses: u64,
+        pub evictions: u64,
+    }
+
+    #[derive(Debug)]
+    pub struct ShardedLfuCache {
+        shards: Vec<Mutex<Shard>>,
+        hasher: RandomState,
+    }
+
+    impl fmt::Display for ShardedLfuCache {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "length: {}", self.len())?;
+            write!(f, "capacity: {}", self.capacity())?;
+            for (i, shard) in self.shards.iter().enumerate() {
+                let shard = lock(shard);
+                write!(f, "\nshard {} ", i)?;
+                write!(
+                    f,
+                    "(hits: {}, misses: {}, evictions: {})",
+                    shard.hits, shard.misses, shard.evictions
+                )?;
+                for (key, val) in shard.cache.iter() {
+                    write!(f, "\n  [ key: {}, value: {} ]", key, val)?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn lock(shard: &Mutex<Shard>) -> MutexGuard<'_, Shard> {
+        unpoisoned(shard.lock())
+    }
+
+    impl ShardedLfuCache {
+        /*   NEVER MORE SHARDS THAN CAPACITY, SO NO SHARD IS LEFT UNABLE TO HOLD A KEY   */
+        pub fn new(capacity: i32, shards: usize) -> Self {
+            let count = shards.min(capacity.max(1) as usize).max(1);
+            let cap = capacity.max(0);
+            let (base, rest) = (cap / count as i32, cap % count as i32);
+            let shards = (0..count)
+                .map(|i| {
+                    let share = if (i as i32) < rest { base + 1 } else { base };
+                    Mutex::new(Shard {
+                        cache: SlabLFUCache::new(share),
+                        hits: 0,
+                        misses: 0,
+                        evictions: 0,
+                    })
+                })
+                .collect();
+            ShardedLfuCache {
+                shards,
+                hasher: RandomState::new(),
+            }
+        }
+
+        fn shard(&self, key: i32) -> MutexGuard<'_, Shard> {
+            let hash = self.hasher.hash_one(key);
+            lock(&self.shards[(hash % self.shards.len() as u64) as usize])
+        }
+
+        pub fn get(&self, key: i32) -> i32 {
+            let mut shard = self.shard(key);
+            let val = shard.cache.get(key);
+            if val == -1 && !shard.cache.contains(key) {
+                shard.misses += 1;
+            } else {
+                shard.hits += 1;
+            }
+            val
+        }
+
+        pub fn put(&self, key: i32, value: i32) {
+            let mut shard = self.shard(key);
+            if shard.cache.insert(key, value).is_some() {
+                shard.evictions += 1;
+            }
+        }
+
+        pub fn remove(&self, key: i32) -> Option<i32> {
+            self.shard(key).cache.remove(key)
+        }
+
+        pub fn len(&self) -> usize {
+            self.shards
+                .iter()
+                .map(|shard| Cache::len(&lock(shard).cache))
+                .sum()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        pub fn capacity(&self) -> usize {
+            self.shards
+                .iter()
+                .map(|shard| Cache::capacity(&lock(shard).cache))
+                .sum()
+        }
+
+        /*   A SNAPSHOT TAKEN ONE SHARD AT A TIME, NOT AN ATOMIC VIEW OF THE WHOLE CACHE   */
+        pub fn iter(&self) -> std::vec::IntoIter<(i32, i32)> {
+            let mut entries = Vec::new();
+            for shard in &self.shards {
+                entries.extend(lock(shard).cache.iter());
+            }
+            entries.into_iter()
+        }
+
+        pub fn stats(&self) -> Vec<ShardStats> {
+            self.shards
+                .iter()
+                .map(|shard| {
+                    let shard = lock(shard);
+                    ShardStats {
+                        len: Cache::len(&shard.cache),
+                        capacity: Cache::capacity(&shard.cache),
+                        hits: shard.hits,
+                        misses: shard.misses,
+                        evictions: shard.evictions,
+                    }
+                })
+                .collect()
+        }
+
+        pub fn clear_cache(&self) {
+            for shard in &self.shards {
+                lock(shard).cache.clear_cache()
+            }
+        }
+    }
+
+    impl Cache<i32, i32> for ShardedLfuCache {
+        fn get(&mut self, key: &i32) -> Option<i32> {
+            let mut shard = self.shard(*key);
+            if shard.cache.contains(*key) {
+                shard.hits += 1;
+                Some(shard.cache.get(*key))
+            } else {
+                shard.misses += 1;
+                None
+            }
+        }
+
+        fn put(&mut self, key: i32, value: i32) {
+            ShardedLfuCache::put(self, key, value)
+        }
+
+        fn remove(&mut self, key: &i32) -> Option<i32> {
+            ShardedLfuCache::remove(self, *key)
+        }
+
+        fn len(&self) -> usize {
+            ShardedLfuCache::len(self)
+        }
+
+        fn capacity(&self) -> usize {
+            ShardedLfuCache::capacity(self)
+        }
+
+        fn clear(&mut self) {
+            self.clear_cache()
+        }
+    }
+}
+
+/*
+    READS THAT TAKE NO LOCK AND NEVER CHANGE A FREQUENCY BUCKET. VALUES ARE
+    SERVED FROM AN OPEN-ADDRESSING TABLE WHOSE SLOTS ARE SEQLOCKS: ONLY THE
+    POLICY LOCK HOLDER WRITES A SLOT, AND A READER THAT SEES A SLOT CHANGE UNDER
+    IT JUST READS IT AGAIN, SO A READ NEVER BLOCKS ON A LOCK OR ON ANOTHER READ.
+    A HIT ONLY RECORDS ITS KEY IN A FIXED RING BUFFER. WHOEVER GETS THE POLICY
+    LOCK WITH `try_lock` ONCE ENOUGH HITS HAVE PILED UP, OR ONCE THE BUFFER IS
+    FULL, REPLAYS THEM THROUGH `move_node`.
+    WRITES TAKE THE POLICY LOCK AND DRAIN THE BUFFER FIRST, SO AN EVICTION SEES
+    EVERY HIT THAT WAS NOT DROPPED.
+
+    THE BUFFER IS LOSSY ON PURPOSE: WHEN IT IS FULL AND MAINTENANCE IS BUSY
+    ELSEWHERE THE HIT IS FORGOTTEN. FREQUENCIES ARE THEREFORE A LOWER BOUND AND
+    LAG BEHIND READS UNTIL THE NEXT DRAIN
+*/
+#[cfg(feature = "std")]
+#[allow(non_snake_case)]
+pub mod BUFFERED {
+    use super::unpoisoned;
+    use super::Cache;
+    use super::SLAB::SlabLFUCache;
+    use std::collections::hash_map::RandomState;
+    use std::fmt;
+    use std::hash::BuildHasher;
+    use std::hint;
+    use std::sync::atomic::{fence, AtomicU32, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+    use std::sync::{Mutex, MutexGuard};
+    use std::vec::Vec;
+
+    const BUFFER: usize = 256;
+    const DRAIN_THRESHOLD: usize = 32;
+    const PRESENT: u64 = 1 << 32;
+
+    /*
+        MANY PRODUCERS CLAIM A SLOT BY BUMPING `tail`, THEN FILL IT. THE SINGLE
+        CONSUMER (THE POLICY LOCK HOLDER) EMPTIES SLOTS FROM `head` AND STOPS AT
+        ONE THAT IS CLAIMED BUT NOT FILLED YET
+    */
+    #[derive(Debug)]
+    struct ReadBuffer {
+        slots: Vec<AtomicU64>,
+        head: AtomicUsize,
+        tail: AtomicUsize,
+    }
+
+    impl ReadBuffer {
+        fn new() -> Self {
+            ReadBuffer {
+                slots: (0..BUFFER).map(|_| AtomicU64::new(0)).collect(),
+                head: AtomicUsize::new(0),
+                tail: AtomicUsize::new(0),
+            }
+        }
+
+        fn pending(&self) -> usize {
+            let tail = self.tail.load(Ordering::Acquire);
+            tail.wrapping_sub(self.head.load(Ordering::Acquire))
+        }
+
+        /*   false IF THE BUFFER IS FULL AND THE ACCESS WAS DROPPED   */
+        fn record(&self, key: i32) -> bool {
+            loop {
+                let tail = self.tail.load(Ordering::Acquire);
+                if tail.wrapping_sub(self.head.load(Ordering::Acquire)) >= BUFFER {
+                    return false;
+                }
+                if self
+                    .tail
+                    .compare_exchange_weak(
+                        tail,
+                        tail.wrapping_add(1),
+                        Ordering::AcqRel,
+                        Ordering::Relaxed,
+                    )
+                    .is_ok()
+                {
+                    self.slots[tail % BUFFER].store(PRESENT | key as u32 as u64, Ordering::Release);
+                    return true;
+                }
+            }
+        }
+
+        /*   ONLY CALLED WITH THE POLICY LOCK HELD   */
+        fn drain<F: FnMut(i32)>(&self, mut replay: F) {
+            let mut head = self.head.load(Ordering::Acquire);
+            let tail = self.tail.load(Ordering::Acquire);
+            while head != tail {
+                let slot = self.slots[head % BUFFER].swap(0, Ordering::AcqRel);
+                if slot == 0 {
+                    break;
+                }
+                replay(slot as u32 as i32);
+                head = head.wrapping_add(1);
+            }
+            self.head.store(head, Ordering::Release);
+        }
+    }
+
+    const EMPTY: u8 = 0;
+    const LIVE: u8 = 1;
+    const REMOVED: u8 = 2;
+
+    /*
+        ONE ENTRY OF THE VALUE TABLE. `seq` IS ODD WHILE THE WRITER CHANGES THE
+        SLOT, AND A READER THAT SEES IT ODD OR SEES IT MOVE READS THE SLOT AGAIN
+    */
+    #[derive(Debug)]
+    struct Slot {
+        seq: AtomicU32,
+        state: AtomicU8,
+        entry: AtomicU64,
+    }
+
+    impl Slot {
+        fn new() -> Self {
+            Slot {
+                seq: AtomicU32::new(0),
+                state: AtomicU8::new(EMPTY),
+                entry: AtomicU64::new(0),
+            }
+        }
+
+        /*   (STATE, KEY, VALUE), ALL FROM THE SAME WRITE   */
+        fn load(&self) -> (u8, i32, i32) {
+            loop {
+                let seq = self.seq.load(Ordering::Acquire);
+                if seq & 1 == 1 {
+                    hint::spin_loop();
+                    continue;
+                }
+                let state = self.state.load(Ordering::Relaxed);
+                let entry = self.entry.load(Ordering::Relaxed);
+                fence(Ordering::Acquire);
+                if self.seq.load(Ordering::Relaxed) == seq {
+                    return (state, (entry >> 32) as u32 as i32, entry as u32 as i32);
+                }
+            }
+        }
+
+        /*   ONLY CALLED WITH THE POLICY LOCK HELD   */
+        fn store(&self, state: u8, key: i32, val: i32) {
+            let seq = self.seq.load(Ordering::Relaxed);
+            self.seq.store(seq.wrapping_add(1), Ordering::Relaxed);
+            fence(Ordering::Release);
+            self.state.store(state, Ordering::Relaxed);
+            self.entry.store(
+                (key as u32 as u64) << 32 | val as u32 as u64,
+                Ordering::Relaxed,
+            );
+            self.seq.store(seq.wrapping_add(2), Ordering::Release);
+        }
+    }
+
+    /*
+        LINEAR PROBING WITH TOMBSTONES, SIZED ONCE FROM THE CAPACITY SO THAT
+        READERS NEVER SEE THE SLOTS MOVE. A LIVE ENTRY STAYS IN ITS SLOT UNTIL
+        IT IS REMOVED, SO A READER PROBING PAST OTHER KEYS CANNOT MISS IT.
+        ONCE TOMBSTONES FILL A QUARTER OF THE TABLE THE WRITER REBUILDS IT IN
+        PLACE UNDER `epoch`, WHICH SENDS EVERY READ THAT OVERLAPPED IT BACK TO
+        THE START
+    */
+    #[derive(Debug)]
+    struct ValueTable {
+        slots: Vec<Slot>,
+        hasher: RandomState,
+        epoch: AtomicU32,
+        len: AtomicUsize,
+        used: AtomicUsize,
+    }
+
+    impl ValueTable {
+        fn new(capacity: i32) -> Self {
+            let size = (capacity.max(0) as usize * 2).max(16).next_power_of_two();
+            ValueTable {
+                slots: (0..size).map(|_| Slot::new()).collect(),
+                hasher: RandomState::new(),
+                epoch: AtomicU32::new(0),
+                len: AtomicUsize::new(0),
+                used: AtomicUsize::new(0),
+            }
+        }
+
+        fn home(&self, key: i32) -> usize {
+            self.hasher.hash_one(key) as usize & (self.slots.len() - 1)
+        }
+
+        fn len(&self) -> usize {
+            self.len.load(Ordering::Relaxed)
+        }
+
+        fn get(&self, key: i32) -> Option<i32> {
+            loop {
+                let epoch = self.epoch.load(Ordering::Acquire);
+                if epoch & 1 == 1 {
+                    hint::spin_loop();
+                    continue;
+                }
+                let found = self.probe(key).map(|(_, val)| val);
+                fence(Ordering::Acquire);
+                if self.epoch.load(Ordering::Relaxed) == epoch {
+                    return found;
+                }
+            }
+        }
+
+        /*   THE SLOT AND VALUE OF A LIVE KEY. BOUNDED SO A TORN REBUILD CANNOT LOOP FOREVER   */
+        fn probe(&self, key: i32) -> Option<(usize, i32)> {
+            let mask = self.slots.len() - 1;
+            let mut i = self.home(key);
+            for _ in 0..self.slots.len() {
+                match self.slots[i].load() {
+                    (EMPTY, _, _) => return None,
+                    (LIVE, k, val) if k == key => return Some((i, val)),
+                    _ => i = (i + 1) & mask,
+                }
+            }
+            None
+        }
+
+        /*   ONLY CALLED WITH THE POLICY LOCK HELD   */
+        fn insert(&self, key: i32, val: i32) {
+            if let Some((i, _)) = self.probe(key) {
+                self.slots[i].store(LIVE, key, val);
+                return;
+            }
+            let mask = self.slots.len() - 1;
+            let mut i = self.home(key);
+            let state = loop {
+                match self.slots[i].load().0 {
+                    LIVE => i = (i + 1) & mask,
+                    state => break state,
+                }
+            };
+            self.slots[i].store(LIVE, key, val);
+            self.len.fetch_add(1, Ordering::Relaxed);
+            if state == EMPTY {
+                let used = self.used.fetch_add(1, Ordering::Relaxed) + 1;
+                if used > self.slots.len() / 4 * 3 {
+                    self.rebuild(true);
+                }
+            }
+        }
+
+        /*   ONLY CALLED WITH THE POLICY LOCK HELD   */
+        fn remove(&self, key: i32) {
+            if let Some((i, _)) = self.probe(key) {
+                self.slots[i].store(REMOVED, key, 0);
+                self.len.fetch_sub(1, Ordering::Relaxed);
+            }
+        }
+
+        /*   ONLY CALLED WITH THE POLICY LOCK HELD. DROPS EVERY TOMBSTONE, AND EVERY ENTRY UNLESS `keep`   */
+        fn rebuild(&self, keep: bool) {
+            let live: Vec<(i32, i32)> = if keep {
+                self.slots
+                    .iter()
+                    .map(Slot::load)
+                    .filter(|&(state, _, _)| state == LIVE)
+                    .map(|(_, key, val)| (key, val))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            let epoch = self.epoch.load(Ordering::Relaxed);
+            self.epoch.store(epoch.wrapping_add(1), Ordering::Relaxed);
+            fence(Ordering::Release);
+            for slot in self.slots.iter() {
+                slot.store(EMPTY, 0, 0);
+            }
+            let mask = self.slots.len() - 1;
+            for &(key, val) in live.iter() {
+                let mut i = self.home(key);
+                while self.slots[i].load().0 != EMPTY {
+                    i = (i + 1) & mask;
+                }
+                self.slots[i].store(LIVE, key, val);
+            }
+            self.len.store(live.len(), Ordering::Relaxed);
+            self.used.store(live.len(), Ordering::Relaxed);
+            self.epoch.store(epoch.wrapping_add(2), Ordering::Release);
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct BufferedLfuCache {
+        values: ValueTable,
+        policy: Mutex<SlabLFUCache>,
+        reads: ReadBuffer,
+    }
+
+    impl fmt::Display for BufferedLfuCache {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", *self.policy())
+        }
+    }
+
+    impl BufferedLfuCache {
+        pub fn new(capacity: i32) -> Self {
+            BufferedLfuCache {
+                values: ValueTable::new(capacity),
+                policy: Mutex::new(SlabLFUCache::new(capacity)),
+                reads: ReadBuffer::new(),
+            }
+        }
+
+        /*   THE POLICY WITH EVERY BUFFERED READ REPLAYED INTO IT   */
+        fn policy(&self) -> MutexGuard<'_, SlabLFUCache> {
+            let mut policy = unpoisoned(self.policy.lock());
+            self.replay(&mut policy);
+            policy
+        }
+
+        /*   A KEY EVICTED OR REMOVED AFTER ITS HIT WAS BUFFERED IS JUST SKIPPED   */
+        fn replay(&self, policy: &mut SlabLFUCache) {
+            self.reads.drain(|key| {
+                if policy.contains(key) {
+                    policy.get(key);
+                }
+            })
+        }
+
+        pub fn maintain(&self) {
+            drop(self.policy())
+        }
+
+        fn try_maintain(&self) {
+            if let Ok(mut policy) = self.policy.try_lock() {
+                self.replay(&mut policy)
+            }
+        }
+
+        /// Takes no lock. Once enough hits have piled up it also tries the
+        /// policy lock to replay them, but never waits for it.
+        pub fn get(&self, key: i32) -> i32 {
+            self.hit(key).unwrap_or(-1)
+        }
+
+        fn hit(&self, key: i32) -> Option<i32> {
+            let val = self.values.get(key)?;
+            if !self.reads.record(key) || self.reads.pending() >= DRAIN_THRESHOLD {
+                self.try_maintain();
+            }
+            Some(val)
+        }
+
+        pub fn put(&self, key: i32, value: i32) {
+            let mut policy = self.policy();
+            if Cache::capacity(&*policy) == 0 {
+                return;
+            }
+            if let Some((victim, _)) = policy.insert(key, value) {
+                self.values.remove(victim);
+            }
+            self.values.insert(key, value);
+        }
+
+        pub fn remove(&self, key: i32) -> Option<i32> {
+            let mut policy = self.policy();
+            let val = policy.remove(key);
+            self.values.remove(key);
+            val
+        }
+
+        pub fn len(&self) -> usize {
+            self.values.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        pub fn capacity(&self) -> usize {
+            Cache::capacity(&*self.policy())
+        }
+
+        pub fn clear_cache(&self) {
+            let mut policy = self.policy();
+            policy.clear_cache();
+            self.values.rebuild(false);
+        }
+    }
+
+    impl Cache<i32, i32> for BufferedLfuCache {
+        fn get(&mut self, key: &i32) -> Option<i32> {
+            self.hit(*key)
+        }
+
+        fn put(&mut self, key: i32, value: i32) {
+            BufferedLfuCache::put(self, key, value)
+        }
+
+        fn remove(&mut self, key: &i32) -> Option<i32> {
+            BufferedLfuCache::remove(self, *key)
+        }
+
+        fn len(&self) -> usize {
+            BufferedLfuCache::len(self)
+        }
+
+        fn capacity(&self) -> usize {
+            BufferedLfuCache::capacity(self)
+        }
+
+        fn clear(&mut self) {
+            self.clear_cache()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::BufferedLfuCache;
+        use crate::rng::XorShift;
+        use std::sync::mpsc::{self, TryRecvError};
+        use std::sync::Arc;
+        use std::thread;
+        use std::time::{Duration, Instant};
+        use std::vec::Vec;
+
+        /*   THE WRITER KEEPS THE POLICY LOCK AND REWRITES THE TABLE AROUND THE KEYS BEING READ   */
+        #[test]
+        fn get_completes_while_a_writer_holds_the_policy_lock() {
+            let cache = Arc::new(BufferedLfuCache::new(64));
+            for key in 0..32 {
+                cache.put(key, key * 2);
+            }
+
+            let policy = cache.policy.lock().unwrap();
+            let (sender, receiver) = mpsc::channel();
+            let reader = {
+                let cache = Arc::clone(&cache);
+                thread::spawn(move || {
+                    for round in 0..10_000 {
+                        let key = round % 32;
+                        assert_eq!(cache.get(key), key * 2);
+                        assert_eq!(cache.get(1000 + key), -1);
+                    }
+                    sender.send(()).unwrap();
+                })
+            };
+
+            let deadline = Instant::now() + Duration::from_secs(10);
+            let mut round = 0;
+            while let Err(TryRecvError::Empty) = receiver.try_recv() {
+                assert!(Instant::now() < deadline, "get waited for the policy lock");
+                let key = 100 + round % 64;
+                cache.values.insert(key, round);
+                cache.values.remove(key);
+                round += 1;
+            }
+            reader.join().unwrap();
+            drop(policy);
+
+            assert_eq!(cache.len(), 32);
+            assert_eq!(cache.get(5), 10);
+        }
+
+        /*   HITS BUFFERED FROM MANY THREADS AT ONCE, THEN A DRAIN MUST LEAVE THE TWO SIDES AGREEING   */
+        #[test]
+        fn values_and_policy_agree_after_maintain() {
+            let cache = Arc::new(BufferedLfuCache::new(64));
+            let threads: Vec<_> = (0..8u64)
+                .map(|t| {
+                    let cache = Arc::clone(&cache);
+                    thread::spawn(move || {
+                        let mut rng = XorShift::new(t);
+                        for _ in 0..20_000 {
+                            let key = rng.below(256) as i32;
+                            match rng.below(16) {
+                                0..=3 => cache.put(key, key * 2),
+                                4 => {
+                                    cache.remove(key);
+                                }
+                                _ => {
+                                    let val = cache.get(key);
+                                    assert!(val == -1 || val == key * 2);
+                                }
+                            }
+                        }
+                    })
+                })
+                .collect();
+            for thread in threads {
+                thread.join().unwrap();
+            }
+
+            cache.maintain();
+            assert_eq!(cache.reads.pending(), 0);
+            let policy = cache.policy();
+            let mut resident = 0;
+            for (key, val) in policy.iter() {
+                assert_eq!(cache.values.get(key), Some(val));
+                resident += 1;
+            }
+            assert_eq!(cache.len(), resident);
+            assert!(resident <= 64);
+        }
+    }
+}
+
+/*
+    LFU WITH ALL OF ITS STORAGE INLINE, SIZED BY THE CONST PARAMETER N. ENTRIES,
+    THEIR LINKS AND THE FREQUENCY BUCKETS ARE ARRAYS OF N, LINKED BY u32 INDEX
+    LIKE THE SLAB CACHE, AND THE KEY INDEX IS N HASH CHAINS THREADED THROUGH THE
+    ENTRY LINKS, SO NOTHING IS EVER ALLOCATED.
+
+    N ENTRIES NEVER HAVE MORE THAN N DISTINCT FREQUENCIES, SO N BUCKETS ARE
+    ENOUGH AS LONG AS A NODE ALONE IN ITS BUCKET TAKES THE BUCKET WITH IT
+    INSTEAD OF ASKING FOR A NEW ONE
+*/
+#[allow(non_snake_case)]
+pub mod ARRAY {
+    use super::indexed::{Bucket, Chain, Link, FREE_BUCKET, NIL, UNLINKED};
+    use super::Cache;
+    use crate::table::FxHasher;
+    use core::fmt;
+    use core::hash::{Hash, Hasher};
+
+    /*   WHERE AN ENTRY IS: ITS BUCKET LINKS AND THE NEXT ENTRY IN ITS HASH CHAIN   */
+    #[derive(Debug, Clone, Copy)]
+    struct Slot {
+        freq: i32,
+        link: Link,
+        chain: u32,
+    }
+
+    const FREE_SLOT: Slot = Slot {
+        freq: 0,
+        link: UNLINKED,
+        chain: NIL,
+    };
+
+    /*   FREE ENTRIES ARE CHAINED THROUGH `slots[..].link.next`, FREE BUCKETS THROUGH `buckets[..].next`   */
+    #[derive(Debug)]
+    pub struct LfuArray<K, V, const N: usize> {
+        entries: [Option<(K, V)>; N],
+        slots: [Slot; N],
+        buckets: [Bucket; N],
+        index: [u32; N],
+        free_entry: u32,
+        free_bucket: u32,
+        head: u32,
+        len: usize,
+    }
+
+    impl<K: fmt::Display, V: fmt::Display, const N: usize> fmt::Display for LfuArray<K, V, N> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "length: {}", self.len)?;
+            write!(f, "capacity: {}", N)?;
+            if self.len == 0 {
+                return write!(f, "\n[empty]");
+            }
+            let mut b = self.head;
+            while b != NIL {
+                let bucket = &self.buckets[b as usize];
+                write!(f, "\nfrequency {} : ", bucket.f)?;
+                let mut n = bucket.head;
+                while n != NIL {
+                    if let Some((key, val)) = &self.entries[n as usize] {
+                        write!(f, "[ key: {}, value: {} ]", key, val)?;
+                    }
+                    n = self.slots[n as usize].link.next;
+                    if n != NIL {
+                        write!(f, " -> ")?;
+                    }
+                }
+                b = bucket.next;
+            }
+            Ok(())
+        }
+    }
+
+    impl<K: Hash + Eq, V, const N: usize> Default for LfuArray<K, V, N> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<K: Hash + Eq, V, const N: usize> LfuArray<K, V, N> {
+        /*   EVALUATED WHEN `new` IS INSTANTIATED, SO A TOO LARGE N FAILS TO COMPILE   */
+        const FITS_U32: () = assert!(N < NIL as usize, "LfuArray capacity must fit in u32");
+
+        pub fn new() -> Self {
+            let () = Self::FITS_U32;
+            let mut cache = LfuArray {
+                entries: core::array::from_fn(|_| None),
+                slots: [FREE_SLOT; N],
+                buckets: [FREE_BUCKET; N],
+                index: [NIL; N],
+                free_entry: NIL,
+                free_bucket: NIL,
+                head: NIL,
+                len: 0,
+            };
+            cache.clear();
+            cache
+        }
+
+        pub fn len(&self) -> usize {
+            self.len
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len == 0
+        }
+
+        pub fn capacity(&self) -> usize {
+            N
+        }
+
+        pub fn clear(&mut self) {
+            for n in 0..N {
+                self.entries[n] = None;
+                self.slots[n] = Slot {
+                    link: Link {
+                        next: if n + 1 < N { n as u32 + 1 } else { NIL },
+                        ..UNLINKED
+                    },
+                    ..FREE_SLOT
+                };
+                self.buckets[n] = Bucket {
+                    next: if n + 1 < N { n as u32 + 1 } else { NIL },
+                    ..FREE_BUCKET
+                };
+                self.index[n] = NIL;
+            }
+            self.free_entry = if N > 0 { 0 } else { NIL };
+            self.free_bucket = self.free_entry;
+            self.head = NIL;
+            self.len = 0;
+        }
+
+        /*   MULTIPLY-SHIFT RANGE REDUCTION, WHICH USES THE WELL MIXED HIGH BITS OF THE HASH   */
+        fn chain_of(key: &K) -> usize {
+            let mut hasher = FxHasher::default();
+            key.hash(&mut hasher);
+            ((hasher.finish() as u128 * N as u128) >> 64) as usize
+        }
+
+        fn find(&self, key: &K) -> u32 {
+            if N == 0 {
+                return NIL;
+            }
+            let mut n = self.index[Self::chain_of(key)];
+            while n != NIL {
+                match &self.entries[n as usize] {
+                    Some((k, _)) if k == key => return n,
+                    _ => n = self.slots[n as usize].chain,
+                }
+            }
+            NIL
+        }
+
+        fn unchain(&mut self, n: u32, key: &K) {
+            let chain = Self::chain_of(key);
+            let next = self.slots[n as usize].chain;
+            if self.index[chain] == n {
+                self.index[chain] = next;
+                return;
+            }
+            let mut prev = self.index[chain];
+            while self.slots[prev as usize].chain != n {
+                prev = self.slots[prev as usize].chain;
+            }
+            self.slots[prev as usize].chain = next;
+        }
+
+        fn move_node(&mut self, n: u32) {
+            let b = self.slots[n as usize].link.parent;
+            self.slots[n as usize].freq += 1;
+            let freq = self.slots[n as usize].freq;
+            let next = self.buckets[b as usize].next;
+            if next != NIL && self.buckets[next as usize].f == freq {
+                self.unlink(n);
+                self.push_back(next, n);
+            } else if self.buckets[b as usize].head == self.buckets[b as usize].tail {
+                self.buckets[b as usize].f = freq;
+            } else {
+                let target = self.new_bucket(freq, b);
+                self.unlink(n);
+                self.push_back(target, n);
+            }
+        }
+
+        fn release(&mut self, n: u32) -> (K, V) {
+            self.unlink(n);
+            let (key, val) = self.entries[n as usize].take().unwrap();
+            self.unchain(n, &key);
+            self.slots[n as usize] = Slot {
+                link: Link {
+                    next: self.free_entry,
+                    ..UNLINKED
+                },
+                ..FREE_SLOT
+            };
+            self.free_entry = n;
+            self.len -= 1;
+            (key, val)
+        }
+
+        pub fn get(&mut self, key: &K) -> Option<&V> {
+            let n = self.find(key);
+            if n == NIL {
+                return None;
+            }
+            self.move_node(n);
+            self.entries[n as usize].as_ref().map(|(_, val)| val)
+        }
+
+        /// Returns the entry evicted to make room, which is the new entry
+        /// itself when N is 0.
+        pub fn put(&mut self, key: K, value: V) -> Option<(K, V)> {
+            if N == 0 {
+                return Some((key, value));
+            }
+            let n = self.find(&key);
+            if n != NIL {
+                if let Some((_, val)) = self.entries[n as usize].as_mut() {
+                    *val = value;
+                }
+                self.move_node(n);
+                return None;
+            }
+            let evicted = if self.len == N {
+                let victim = self.buckets[self.head as usize].head;
+                Some(self.release(victim))
+            } else {
+                None
+            };
+            let n = self.free_entry;
+            self.free_entry = self.slots[n as usize].link.next;
+            let chain = Self::chain_of(&key);
+            self.slots[n as usize] = Slot {
+                freq: 1,
+                chain: self.index[chain],
+                ..FREE_SLOT
+            };
+            self.index[chain] = n;
+            self.entries[n as usize] = Some((key, value));
+            let b = if self.head != NIL && self.buckets[self.head as usize].f == 1 {
+                self.head
+            } else {
+                self.new_bucket(1, NIL)
+            };
+            self.push_back(b, n);
+            self.len += 1;
+            evicted
+        }
+
+        pub fn remove(&mut self, key: &K) -> Option<V> {
+            let n = self.find(key);
+            if n == NIL {
+                return None;
+            }
+            Some(self.release(n).1)
+        }
+    }
+
+    impl<K, V, const N: usize> Chain for LfuArray<K, V, N> {
+        fn link(&mut self, n: u32) -> &mut Link {
+            &mut self.slots[n as usize].link
+        }
+
+        fn bucket(&mut self, b: u32) -> &mut Bucket {
+            &mut self.buckets[b as usize]
+        }
+
+        fn head(&mut self) -> &mut u32 {
+            &mut self.head
+        }
+
+        /*   N BUCKETS ARE ALWAYS ENOUGH, SEE ABOVE, SO THE FREE LIST NEVER RUNS DRY   */
+        fn alloc_bucket(&mut self, bucket: Bucket) -> u32 {
+            let b = self.free_bucket;
+            self.free_bucket = self.buckets[b as usize].next;
+            self.buckets[b as usize] = bucket;
+            b
+        }
+
+        fn release_bucket(&mut self, b: u32) {
+            self.buckets[b as usize] = Bucket {
+                next: self.free_bucket,
+                ..FREE_BUCKET
+            };
+            self.free_bucket = b;
+        }
+    }
+
+    impl<K, V, const N: usize> Cache<K, V> for LfuArray<K, V, N>
+    where
+        K: Hash + Eq + fmt::Display,
+        V: Clone + fmt::Display,
+    {
+        fn get(&mut self, key: &K) -> Option<V> {
+            LfuArray::get(self, key).cloned()
+        }
+
+        fn put(&mut self, key: K, value: V) {
+            LfuArray::put(self, key, value);
+        }
+
+        fn remove(&mut self, key: &K) -> Option<V> {
+            LfuArray::remove(self, key)
+        }
+
+        fn len(&self) -> usize {
+            self.len
+        }
+
+        fn capacity(&self) -> usize {
+            N
+        }
+
+        fn clear(&mut self) {
+            LfuArray::clear(self)
+        }
     }
 }
@@ -1,22 +1,64 @@
 pub mod process {
-    use crate::cache;
+    use lfu::cache;
+    use lfu::cache::Cache;
+    use lfu::simulator;
     use std::process::exit;
 
     const CREATE: &str = "create";
     const INSERT: &str = "insert";
     const GET: &str = "get";
+    const REMOVE: &str = "remove";
+    const SIMULATE: &str = "simulate";
     const SHOW: &str = "show\n";
     const CLEAR: &str = "clear\n";
     const EXIT: &str = "exit\n";
 
-    pub fn handle_input(lfu_cache: &mut cache::LFU::LFUCache, input: &mut String) {
-        let keywords: Vec<&str> = input.split(" ").collect();
+    const LFU: &str = "lfu";
+    const LRU: &str = "lru";
+    const FIFO: &str = "fifo";
+    const CLOCK: &str = "clock";
+    const ARC: &str = "arc";
+    const SAMPLED: &str = "sampled";
+    const LRFU: &str = "lrfu";
+
+    /// `lambda` is required by `lrfu` and rejected by every other policy.
+    pub fn new_cache(
+        capacity: i32,
+        policy: &str,
+        lambda: Option<f64>,
+    ) -> Option<Box<dyn Cache<i32, i32>>> {
+        match (policy, lambda) {
+            (LFU, None) => Some(Box::new(cache::LFU::LFUCache::new(capacity))),
+            (LRU, None) => Some(Box::new(cache::LRU::LRUCache::new(capacity))),
+            (FIFO, None) => Some(Box::new(cache::FIFO::FIFOCache::new(capacity))),
+            (CLOCK, None) => Some(Box::new(cache::CLOCK::CLOCKCache::new(capacity))),
+            (ARC, None) => Some(Box::new(cache::ARC::ARCCache::new(capacity))),
+            (SAMPLED, None) => Some(Box::new(cache::SAMPLED::SampledLFUCache::new(capacity))),
+            (LRFU, Some(lambda)) => Some(Box::new(cache::LRFU::LRFUCache::new(capacity, lambda))),
+            _ => None,
+        }
+    }
+
+    /// Runs one command against `cache`. `create` does not modify it but
+    /// returns the new cache for the caller to use from then on.
+    pub fn handle_input(
+        cache: &mut dyn Cache<i32, i32>,
+        input: &str,
+    ) -> Option<Box<dyn Cache<i32, i32>>> {
+        let keywords: Vec<&str> = input.split(' ').collect();
 
         match keywords[0] {
             CREATE => match keywords.get(1) {
                 Some(capacity) => {
                     let cap = capacity.trim().parse().expect("not a number");
-                    *lfu_cache = cache::LFU::LFUCache::new(cap);
+                    let policy = keywords.get(2).map_or(LFU, |policy| policy.trim());
+                    let lambda = keywords
+                        .get(3)
+                        .map(|lambda| lambda.trim().parse().expect("not a number"));
+                    match new_cache(cap, policy, lambda) {
+                        Some(new) => return Some(new),
+                        None => println!("{}", commands()),
+                    }
                 }
                 None => println!("{}", commands()),
             },
@@ -25,7 +67,7 @@ pub mod process {
                     Some(value) => {
                         let k: i32 = key.trim().parse().expect("not a number");
                         let v: i32 = value.trim().parse().expect("not a number");
-                        lfu_cache.put(k, v)
+                        cache.put(k, v)
                     }
                     None => println!("{}", commands()),
                 },
@@ -34,27 +76,104 @@ pub mod process {
             GET => match keywords.get(1) {
                 Some(key) => {
                     let k: i32 = key.trim().parse().expect("not a number");
-                    println!("{}", lfu_cache.get(k))
+                    println!("{}", cache.get(&k).unwrap_or(-1))
                 }
                 None => println!("{}", commands()),
             },
-            CLEAR => lfu_cache.clear_cache(),
+            REMOVE => match keywords.get(1) {
+                Some(key) => {
+                    let k: i32 = key.trim().parse().expect("not a number");
+                    println!("{}", cache.remove(&k).unwrap_or(-1))
+                }
+                None => println!("{}", commands()),
+            },
+            SIMULATE => match keywords.get(1) {
+                Some(capacity) => {
+                    let cap: i32 = capacity.trim().parse().expect("not a number");
+                    let trace: Vec<i32> = keywords[2..]
+                        .iter()
+                        .map(|key| key.trim().parse().expect("not a number"))
+                        .collect();
+                    println!("{}", simulator::OPT::compare(&trace, cap))
+                }
+                None => println!("{}", commands()),
+            },
+            CLEAR => cache.clear(),
             EXIT => exit(0),
-            SHOW => println!("{}", lfu_cache),
+            SHOW => println!("{}", cache),
             _ => println!("{}", commands()),
         }
+        None
     }
 
     pub fn commands() -> String {
-        format!(
+        String::from(
             "
-create [capacity]          create LFUCache holder with given capacity
+create [capacity] [policy] [lambda]
+                           create cache holder with given capacity; policy is one of
+                           lfu (default), lru, fifo, clock, arc, sampled, or lrfu
+                           followed by its decay lambda (0 is lfu, 1 is lru)
 insert [key] [value]       insert or modify value by key
 get [key]                  extract value by key; returns -1 in case of wrong key
+remove [key]               remove value by key; returns -1 in case of wrong key
+simulate [capacity] [keys] compare the hit ratio of lfu on a key trace with the optimal one
 show                       display cache nodes
 clear                      remove all values from cache
 exit                       close program
-"
+",
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::process::handle_input;
+    use lfu::cache::Cache;
+    use lfu::cache::LFU::LFUCache;
+    use lfu::simulator::OPT::replay;
+
+    fn create(input: &str) -> Option<Box<dyn Cache<i32, i32>>> {
+        handle_input(&mut LFUCache::new(0), input)
+    }
+
+    /*
+        WHETHER KEY 1 IS STILL CACHED AFTER EACH TRACE, REPLAYED ON A NEW
+        CACHE OF 2. THE ANSWERS TELL LFU, LRU, FIFO AND CLOCK APART
+    */
+    fn keeps_key_one(input: &str) -> Vec<bool> {
+        let traces: [&[i32]; 3] = [&[1, 1, 2, 3], &[1, 2, 1, 3], &[1, 1, 1, 2, 2, 3]];
+        traces
+            .iter()
+            .map(|trace| {
+                let mut cache = create(input).unwrap();
+                assert_eq!(cache.capacity(), 2, "{}", input);
+                replay(cache.as_mut(), trace);
+                cache.get(&1).is_some()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn create_builds_the_named_policy() {
+        assert_eq!(keeps_key_one("create 2\n"), [true, true, true]);
+        assert_eq!(keeps_key_one("create 2 lfu\n"), [true, true, true]);
+        assert_eq!(keeps_key_one("create 2 lru\n"), [false, true, false]);
+        assert_eq!(keeps_key_one("create 2 fifo\n"), [false, false, false]);
+        assert_eq!(keeps_key_one("create 2 clock\n"), [true, true, false]);
+        assert_eq!(keeps_key_one("create 2 lrfu 0\n"), [true, true, true]);
+        assert_eq!(keeps_key_one("create 2 lrfu 1\n"), [false, true, false]);
+        for policy in ["arc", "sampled"].iter() {
+            let cache = create(&format!("create 5 {}\n", policy)).unwrap();
+            assert_eq!(cache.capacity(), 5);
+        }
+    }
+
+    #[test]
+    fn create_rejects_unknown_policies() {
+        assert!(create("create 2 mru\n").is_none());
+        assert!(create("create 2 LFU\n").is_none());
+        assert!(create("create\n").is_none());
+        assert!(create("create 2 lrfu\n").is_none());
+        assert!(create("create 2 lfu 0.5\n").is_none());
+    }
+}
@@ -0,0 +1,10 @@
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod cache;
+mod rng;
+pub mod simulator;
+mod table;
@@ -1,4 +1,3 @@
-mod cache;
 mod helper;
 
 use std::io;
@@ -7,12 +6,14 @@ fn main() {
     let message = helper::process::commands();
     println!("{}", message);
 
-    let mut lfu_cache = cache::LFU::LFUCache::new(0);
+    let mut cache = helper::process::new_cache(0, "lfu", None).unwrap();
     loop {
         let mut input = String::new();
         io::stdin()
             .read_line(&mut input)
             .expect("failed to read input");
-        helper::process::handle_input(&mut lfu_cache, &mut input)
+        if let Some(new) = helper::process::handle_input(cache.as_mut(), &input) {
+            cache = new
+        }
     }
 }
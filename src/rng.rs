@@ -0,0 +1,32 @@
+/*
+    XORSHIFT64 PRNG FOR THE RANDOMIZED POLICIES. NOT CRYPTOGRAPHIC, JUST CHEAP
+    AND REPRODUCIBLE. THE BENCHMARKS AND TESTS PULL THIS FILE IN WITH `#[path]`
+    SO THERE IS ONLY EVER ONE GENERATOR IN THE TREE
+*/
+
+#[derive(Debug, Clone)]
+pub(crate) struct XorShift(u64);
+
+impl XorShift {
+    /*   SPREAD SMALL SEEDS OVER THE STATE, AND NEVER START AT 0, WHERE XORSHIFT STAYS   */
+    pub(crate) fn new(seed: u64) -> Self {
+        XorShift(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /*   UNIFORM IN [0, 1) FROM THE TOP 53 BITS   */
+    #[cfg(feature = "std")]
+    pub(crate) fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub(crate) fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
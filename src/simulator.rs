@@ -0,0 +1,204 @@
+/*
+    OFFLINE BELADY (OPT) SIMULATION.
+    KNOWING THE WHOLE TRACE IN ADVANCE, OPT EVICTS THE RESIDENT KEY WHOSE
+    NEXT USE IS FURTHEST AWAY. NO ONLINE POLICY CAN HAVE MORE HITS ON THE
+    SAME TRACE, SO ITS HIT RATIO IS THE CEILING TO JUDGE `LFUCache` AGAINST
+*/
+#[allow(non_snake_case)]
+pub mod OPT {
+    use crate::cache::Cache;
+    use crate::cache::LFU::LFUCache;
+    use crate::table::HashMap;
+    use alloc::collections::BTreeSet;
+    use alloc::vec;
+    use core::fmt;
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Report {
+        pub accesses: usize,
+        pub optimal_hits: usize,
+        pub lfu_hits: usize,
+    }
+
+    fn ratio(hits: usize, accesses: usize) -> f64 {
+        if accesses == 0 {
+            return 0.0;
+        }
+        hits as f64 / accesses as f64
+    }
+
+    impl Report {
+        pub fn optimal_ratio(&self) -> f64 {
+            ratio(self.optimal_hits, self.accesses)
+        }
+
+        pub fn lfu_ratio(&self) -> f64 {
+            ratio(self.lfu_hits, self.accesses)
+        }
+    }
+
+    impl fmt::Display for Report {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "accesses: {}", self.accesses)?;
+            writeln!(
+                f,
+                "optimal hit ratio: {:.3} ({} hits)",
+                self.optimal_ratio(),
+                self.optimal_hits
+            )?;
+            write!(
+                f,
+                "lfu hit ratio: {:.3} ({} hits)",
+                self.lfu_ratio(),
+                self.lfu_hits
+            )
+        }
+    }
+
+    /// Number of hits Belady's algorithm gets on `trace`. Every miss loads
+    /// the key, like a `get` followed by a `put` would.
+    pub fn optimal_hits(trace: &[i32], capacity: i32) -> usize {
+        if capacity <= 0 {
+            return 0;
+        }
+
+        /*   POSITION OF THE NEXT ACCESS TO THE SAME KEY, usize::MAX FOR NEVER   */
+        let mut next_use = vec![usize::MAX; trace.len()];
+        let mut seen: HashMap<i32, usize> = HashMap::new();
+        for (i, key) in trace.iter().enumerate().rev() {
+            if let Some(next) = seen.insert(*key, i) {
+                next_use[i] = next;
+            }
+        }
+
+        let mut hits = 0;
+        let mut resident: HashMap<i32, usize> = HashMap::new();
+        let mut by_next_use: BTreeSet<(usize, i32)> = BTreeSet::new();
+        for (i, key) in trace.iter().enumerate() {
+            match resident.get(key) {
+                Some(next) => {
+                    hits += 1;
+                    by_next_use.remove(&(*next, *key));
+                }
+                None => {
+                    if resident.len() as i32 == capacity {
+                        let furthest = *by_next_use.iter().next_back().unwrap();
+                        by_next_use.remove(&furthest);
+                        resident.remove(&furthest.1);
+                    }
+                }
+            }
+            resident.insert(*key, next_use[i]);
+            by_next_use.insert((next_use[i], *key));
+        }
+        hits
+    }
+
+    /// Number of hits `cache` gets on `trace`, putting every missed key.
+    pub fn replay(cache: &mut dyn Cache<i32, i32>, trace: &[i32]) -> usize {
+        let mut hits = 0;
+        for key in trace {
+            match cache.get(key) {
+                Some(_) => hits += 1,
+                None => cache.put(*key, *key),
+            }
+        }
+        hits
+    }
+
+    pub fn compare(trace: &[i32], capacity: i32) -> Report {
+        Report {
+            accesses: trace.len(),
+            optimal_hits: optimal_hits(trace, capacity),
+            lfu_hits: replay(&mut LFUCache::new(capacity), trace),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{compare, optimal_hits};
+        use alloc::vec::Vec;
+        use core::mem;
+
+        /*   TRIES EVERY CHOICE OF VICTIM ON EVERY MISS   */
+        fn brute_force(trace: &[i32], capacity: usize, resident: &mut Vec<i32>) -> usize {
+            let (&key, rest) = match trace.split_first() {
+                Some(split) => split,
+                None => return 0,
+            };
+            if resident.contains(&key) {
+                return 1 + brute_force(rest, capacity, resident);
+            }
+            if resident.len() < capacity {
+                resident.push(key);
+                let hits = brute_force(rest, capacity, resident);
+                resident.pop();
+                return hits;
+            }
+            (0..resident.len())
+                .map(|i| {
+                    let evicted = mem::replace(&mut resident[i], key);
+                    let hits = brute_force(rest, capacity, resident);
+                    resident[i] = evicted;
+                    hits
+                })
+                .max()
+                .unwrap_or(0)
+        }
+
+        /*   EVERY TRACE OF UP TO 7 ACCESSES OVER 3 KEYS   */
+        fn traces() -> Vec<Vec<i32>> {
+            let mut traces = Vec::new();
+            for len in 0..=7u32 {
+                for mut n in 0..3usize.pow(len) {
+                    let trace = (0..len)
+                        .map(|_| {
+                            let key = (n % 3) as i32;
+                            n /= 3;
+                            key
+                        })
+                        .collect();
+                    traces.push(trace);
+                }
+            }
+            traces
+        }
+
+        #[test]
+        fn optimal_hits_match_brute_force() {
+            for trace in traces() {
+                for capacity in 0..=3 {
+                    assert_eq!(
+                        optimal_hits(&trace, capacity),
+                        brute_force(&trace, capacity as usize, &mut Vec::new()),
+                        "{:?} with capacity {}",
+                        trace,
+                        capacity
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn capacity_zero_never_hits_and_one_only_hits_repeats() {
+            let trace = [1, 1, 2, 1, 1, 1, 3, 3, 2];
+            assert_eq!(optimal_hits(&trace, 0), 0);
+            assert_eq!(optimal_hits(&trace, -1), 0);
+            assert_eq!(optimal_hits(&trace, 1), 4);
+            assert_eq!(optimal_hits(&[7; 5], 1), 4);
+            assert_eq!(optimal_hits(&[], 1), 0);
+        }
+
+        #[test]
+        fn lfu_never_beats_the_optimum() {
+            for trace in traces() {
+                for capacity in 0..=3 {
+                    let report = compare(&trace, capacity);
+                    assert_eq!(report.accesses, trace.len());
+                    assert!(report.lfu_hits <= report.optimal_hits, "{:?}", trace);
+                    assert!(report.lfu_ratio() <= report.optimal_ratio(), "{:?}", trace);
+                }
+            }
+        }
+    }
+}
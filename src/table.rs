@@ -0,0 +1,301 @@
+/*
+    THE HASH TABLE BEHIND EVERY CACHE'S KEY INDEX. WITH `std` IT IS THE
+    STANDARD `HashMap`; WITHOUT IT, A SMALL OPEN-ADDRESSING TABLE WITH LINEAR
+    PROBING AND BACKWARD-SHIFT DELETION, SO REMOVALS LEAVE NO TOMBSTONES AND
+    LOOKUPS STAY SHORT UNDER THE CONSTANT CHURN OF A CACHE
+*/
+use core::hash::Hasher;
+
+#[cfg(feature = "std")]
+pub(crate) use std::collections::HashMap;
+
+#[cfg(not(feature = "std"))]
+pub(crate) use self::open::HashMap;
+
+/*   FX HASH: ONE ROTATE, XOR AND MULTIPLY PER WORD. FAST, NOT DOS RESISTANT   */
+#[derive(Default)]
+pub(crate) struct FxHasher(u64);
+
+impl Hasher for FxHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_u64(byte as u64);
+        }
+    }
+
+    fn write_u32(&mut self, n: u32) {
+        self.write_u64(n as u64);
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.0 = (self.0.rotate_left(5) ^ n).wrapping_mul(0x517c_c1b7_2722_0a95);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(not(feature = "std"))]
+mod open {
+    use super::FxHasher;
+    use alloc::vec::Vec;
+    use core::borrow::Borrow;
+    use core::fmt;
+    use core::hash::{Hash, Hasher};
+    use core::mem;
+    use core::ops::Index;
+
+    pub(crate) struct HashMap<K, V> {
+        slots: Vec<Option<(K, V)>>,
+        len: usize,
+    }
+
+    impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for HashMap<K, V> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_map().entries(self.iter()).finish()
+        }
+    }
+
+    impl<K, V> Default for HashMap<K, V> {
+        fn default() -> Self {
+            HashMap {
+                slots: Vec::new(),
+                len: 0,
+            }
+        }
+    }
+
+    impl<K: Hash + Eq, V> HashMap<K, V> {
+        pub(crate) fn new() -> Self {
+            Self::default()
+        }
+
+        pub(crate) fn len(&self) -> usize {
+            self.len
+        }
+
+        pub(crate) fn is_empty(&self) -> bool {
+            self.len == 0
+        }
+
+        pub(crate) fn clear(&mut self) {
+            self.slots.iter_mut().for_each(|slot| *slot = None);
+            self.len = 0;
+        }
+
+        fn home<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+            let mut hasher = FxHasher::default();
+            key.hash(&mut hasher);
+            /*   THE MULTIPLY LEAVES THE BEST BITS AT THE TOP, SO TAKE THE INDEX FROM THERE   */
+            let bits = self.slots.len().trailing_zeros();
+            (hasher.finish() >> (64 - bits)) as usize
+        }
+
+        fn find<Q>(&self, key: &Q) -> Option<usize>
+        where
+            K: Borrow<Q>,
+            Q: Hash + Eq + ?Sized,
+        {
+            if self.len == 0 {
+                return None;
+            }
+            let mask = self.slots.len() - 1;
+            let mut i = self.home(key);
+            loop {
+                match &self.slots[i] {
+                    Some((k, _)) if k.borrow() == key => return Some(i),
+                    Some(_) => i = (i + 1) & mask,
+                    None => return None,
+                }
+            }
+        }
+
+        /*   ROOM FOR `additional` MORE KEYS, KEEPING THE LOAD AT OR BELOW 3/4 AND THE SIZE A POWER OF TWO   */
+        pub(crate) fn reserve(&mut self, additional: usize) {
+            let mut size = self.slots.len();
+            while (self.len + additional) * 4 > size * 3 {
+                size = (size * 2).max(8);
+            }
+            if size == self.slots.len() {
+                return;
+            }
+            let old = mem::replace(&mut self.slots, (0..size).map(|_| None).collect());
+            for (key, val) in old.into_iter().flatten() {
+                let mask = size - 1;
+                let mut i = self.home(&key);
+                while self.slots[i].is_some() {
+                    i = (i + 1) & mask;
+                }
+                self.slots[i] = Some((key, val));
+            }
+        }
+
+        pub(crate) fn insert(&mut self, key: K, val: V) -> Option<V> {
+            if let Some(i) = self.find(&key) {
+                return self.slots[i].as_mut().map(|(_, v)| mem::replace(v, val));
+            }
+            self.reserve(1);
+            let mask = self.slots.len() - 1;
+            let mut i = self.home(&key);
+            while self.slots[i].is_some() {
+                i = (i + 1) & mask;
+            }
+            self.slots[i] = Some((key, val));
+            self.len += 1;
+            None
+        }
+
+        pub(crate) fn get<Q>(&self, key: &Q) -> Option<&V>
+        where
+            K: Borrow<Q>,
+            Q: Hash + Eq + ?Sized,
+        {
+            let i = self.find(key)?;
+            self.slots[i].as_ref().map(|(_, v)| v)
+        }
+
+        pub(crate) fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+        where
+            K: Borrow<Q>,
+            Q: Hash + Eq + ?Sized,
+        {
+            let i = self.find(key)?;
+            self.slots[i].as_mut().map(|(_, v)| v)
+        }
+
+        pub(crate) fn contains_key<Q>(&self, key: &Q) -> bool
+        where
+            K: Borrow<Q>,
+            Q: Hash + Eq + ?Sized,
+        {
+            self.find(key).is_some()
+        }
+
+        /*   PULLS EVERY FOLLOWING ENTRY THAT IS AWAY FROM ITS HOME ONE SLOT BACK   */
+        pub(crate) fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where
+            K: Borrow<Q>,
+            Q: Hash + Eq + ?Sized,
+        {
+            let mut hole = self.find(key)?;
+            let (_, val) = self.slots[hole].take()?;
+            self.len -= 1;
+            let mask = self.slots.len() - 1;
+            let mut i = (hole + 1) & mask;
+            while let Some((k, _)) = &self.slots[i] {
+                let home = self.home(k);
+                if (i.wrapping_sub(home) & mask) >= (i.wrapping_sub(hole) & mask) {
+                    self.slots[hole] = self.slots[i].take();
+                    hole = i;
+                }
+                i = (i + 1) & mask;
+            }
+            Some(val)
+        }
+    }
+
+    impl<K, V> HashMap<K, V> {
+        pub(crate) fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+            self.slots
+                .iter()
+                .filter_map(|slot| slot.as_ref().map(|(k, v)| (k, v)))
+        }
+    }
+
+    impl<K, Q, V> Index<&Q> for HashMap<K, V>
+    where
+        K: Hash + Eq + Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        type Output = V;
+
+        fn index(&self, key: &Q) -> &V {
+            self.get(key).expect("no entry found for key")
+        }
+    }
+}
+
+#[cfg(all(test, not(feature = "std")))]
+mod tests {
+    use super::open::HashMap;
+    use super::FxHasher;
+    use alloc::vec::Vec;
+    use core::hash::{Hash, Hasher};
+
+    /*   KEYS WHOSE HOME SLOT IN A TABLE OF 8 IS `slot`   */
+    fn homing_at(slot: usize, count: usize) -> Vec<i32> {
+        (0..)
+            .filter(|key: &i32| {
+                let mut hasher = FxHasher::default();
+                key.hash(&mut hasher);
+                (hasher.finish() >> 61) as usize == slot
+            })
+            .take(count)
+            .collect()
+    }
+
+    #[test]
+    fn insert_overwrite_and_remove() {
+        let mut map = HashMap::new();
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.remove(&1), None);
+        assert_eq!(map.insert(1, 10), None);
+        assert_eq!(map.insert(2, 20), None);
+        assert_eq!(map.insert(1, 11), Some(10));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[&1], 11);
+        *map.get_mut(&2).unwrap() += 1;
+        assert_eq!(map.remove(&2), Some(21));
+        assert_eq!(map.remove(&2), None);
+        assert!(!map.contains_key(&2));
+        assert_eq!(map.len(), 1);
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.get(&1), None);
+    }
+
+    /*   THE CLUSTER STARTS IN THE LAST SLOT AND WRAPS, SO THE SHIFT HAS TO WRAP TOO   */
+    #[test]
+    fn removal_inside_a_cluster_keeps_the_rest_reachable() {
+        let mut keys = homing_at(7, 3);
+        keys.extend(homing_at(0, 2));
+        for order in 0..keys.len() {
+            let mut map = HashMap::new();
+            for key in keys.iter() {
+                map.insert(*key, *key * 2);
+            }
+            assert_eq!(map.remove(&keys[order]), Some(keys[order] * 2));
+            for key in keys.iter().filter(|key| **key != keys[order]) {
+                assert_eq!(map.get(key), Some(&(*key * 2)));
+            }
+            assert_eq!(map.len(), keys.len() - 1);
+
+            /*   THE FREED SLOT IS USABLE AND NOTHING WAS LEFT BEHIND   */
+            map.insert(keys[order], 0);
+            assert_eq!(map.iter().count(), keys.len());
+        }
+    }
+
+    #[test]
+    fn reserve_grows_without_losing_entries() {
+        let mut map = HashMap::new();
+        for key in 0..5 {
+            map.insert(key, key);
+        }
+        map.reserve(1000);
+        for key in 0..5 {
+            assert_eq!(map[&key], key);
+        }
+        for key in 5..1005 {
+            map.insert(key, key);
+        }
+        assert_eq!(map.len(), 1005);
+        for key in (0..1005).step_by(2) {
+            assert_eq!(map.remove(&key), Some(key));
+        }
+        for key in 0..1005 {
+            assert_eq!(map.get(&key), if key % 2 == 1 { Some(&key) } else { None });
+        }
+    }
+}
@@ -0,0 +1,85 @@
+/*
+    A FULL `LFUCache` REUSES EVICTED NODES AND EMPTIED BUCKETS, SO ONCE IT HAS
+    WARMED UP, HITS, MISSES AND EVICTIONS MUST NOT ALLOCATE AT ALL.
+    THE GLOBAL ALLOCATOR COUNTS ALLOCATIONS, BUT ONLY ON A THREAD THAT ASKED FOR IT,
+    SO THE TEST HARNESS RUNNING ON OTHER THREADS DOES NOT SHOW UP IN THE COUNT
+*/
+mod common;
+
+use common::Rng;
+use lfu::cache::Cache;
+use lfu::cache::LFU::LFUCache;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct Counting;
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count() {
+    if COUNTING.try_with(Cell::get).unwrap_or(false) {
+        let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+    }
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/*   ALLOCATIONS MADE BY `f` ON THIS THREAD   */
+fn allocations(f: impl FnOnce()) -> usize {
+    ALLOCATIONS.with(|n| n.set(0));
+    COUNTING.with(|c| c.set(true));
+    f();
+    COUNTING.with(|c| c.set(false));
+    ALLOCATIONS.with(Cell::get)
+}
+
+/*   HALF THE ACCESSES GO TO A HOT SET THAT FITS, THE REST MISS AND EVICT   */
+fn run(cache: &mut LFUCache, rng: &mut Rng, ops: usize) {
+    for _ in 0..ops {
+        let key = if rng.below(2) == 0 {
+            rng.below(16)
+        } else {
+            rng.below(16_384)
+        };
+        if cache.get(key) == -1 {
+            cache.put(key, key);
+        }
+    }
+}
+
+#[test]
+fn steady_state_at_capacity_does_not_allocate() {
+    let mut cache = LFUCache::new(128);
+    let mut rng = Rng::new(0);
+    run(&mut cache, &mut rng, 20_000);
+    assert_eq!(cache.len(), 128);
+
+    let allocated = allocations(|| run(&mut cache, &mut rng, 20_000));
+    assert_eq!(allocated, 0, "{} allocations after warm-up", allocated);
+    assert_eq!(cache.len(), 128);
+}
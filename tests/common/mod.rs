@@ -0,0 +1,18 @@
+/*   THE CRATE'S OWN XORSHIFT, DRAWING i32 KEYS AND VALUES FOR SEEDED OPERATION SEQUENCES   */
+#[path = "../../src/rng.rs"]
+#[allow(dead_code)]
+mod rng;
+
+use rng::XorShift;
+
+pub struct Rng(XorShift);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(XorShift::new(seed))
+    }
+
+    pub fn below(&mut self, n: u64) -> i32 {
+        self.0.below(n) as i32
+    }
+}
@@ -0,0 +1,446 @@
+/*
+    DIFFERENTIAL TEST OF THE CACHES AGAINST SIMPLER REFERENCE MODELS.
+    SEEDED RANDOM SEQUENCES OF put/get/clear_cache/resize RUN AGAINST BOTH, AND
+    AFTER EVERY OP THE GET RESULT AND THE LENGTH MUST AGREE. ON A DIVERGENCE THE
+    SEQUENCE IS SHRUNK BY DROPPING EVER SMALLER CHUNKS OF OPS WHILE IT STILL
+    DIVERGES, AND THE SHORTEST ONE FOUND IS REPORTED.
+
+    `LFU_DIFF_SEEDS=<n>` RUNS MORE SEEDS THAN THE DEFAULT
+*/
+mod common;
+
+use common::Rng;
+use lfu::cache::Cache;
+use lfu::cache::ARRAY::LfuArray;
+use lfu::cache::LFU::LFUCache;
+use lfu::cache::LRFU::LRFUCache;
+use lfu::cache::LRU::LRUCache;
+use lfu::cache::SHARDED::ShardedLfuCache;
+use lfu::cache::SLAB::SlabLFUCache;
+use lfu::cache::SYNC::SyncLfuCache;
+use std::fmt::Write;
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Put(i32, i32),
+    Get(i32),
+    Clear,
+    Resize(i32),
+}
+
+trait Model {
+    fn create(capacity: i32) -> Self;
+    fn apply(&mut self, op: Op) -> i32;
+    fn size(&self) -> usize;
+}
+
+impl Model for LFUCache {
+    fn create(capacity: i32) -> Self {
+        LFUCache::new(capacity)
+    }
+
+    fn apply(&mut self, op: Op) -> i32 {
+        match op {
+            Op::Put(key, value) => self.put(key, value),
+            Op::Get(key) => return self.get(key),
+            Op::Clear => self.clear_cache(),
+            Op::Resize(capacity) => self.resize(capacity),
+        }
+        0
+    }
+
+    fn size(&self) -> usize {
+        Cache::len(self)
+    }
+}
+
+/*
+    TEXTBOOK LFU TO CHECK `LFUCache` AGAINST. NO BUCKETS, NO LINKS: EVERY ENTRY
+    CARRIES ITS FREQUENCY AND THE TICK IT LAST CHANGED FREQUENCY, AND EVICTION
+    SCANS FOR THE LOWEST (FREQUENCY, TICK). THAT IS THE SAME TIE-BREAK AS
+    `LFUCache`, WHERE A NODE JOINS THE BACK OF ITS BUCKET WHENEVER ITS
+    FREQUENCY CHANGES AND EVICTION TAKES THE FRONT
+*/
+struct Entry {
+    key: i32,
+    val: i32,
+    freq: i32,
+    tick: u64,
+}
+
+struct NaiveLfu {
+    cap: i32,
+    tick: u64,
+    entries: Vec<Entry>,
+}
+
+impl NaiveLfu {
+    fn position(&self, key: i32) -> Option<usize> {
+        self.entries.iter().position(|e| e.key == key)
+    }
+
+    fn touch(&mut self, i: usize) {
+        self.tick += 1;
+        self.entries[i].freq += 1;
+        self.entries[i].tick = self.tick;
+    }
+
+    fn evict(&mut self) {
+        let victim = (0..self.entries.len())
+            .min_by_key(|&i| (self.entries[i].freq, self.entries[i].tick))
+            .unwrap();
+        self.entries.remove(victim);
+    }
+
+    fn get(&mut self, key: i32) -> i32 {
+        match self.position(key) {
+            Some(i) => {
+                self.touch(i);
+                self.entries[i].val
+            }
+            None => -1,
+        }
+    }
+
+    fn put(&mut self, key: i32, value: i32) {
+        if self.cap == 0 {
+            return;
+        }
+        if let Some(i) = self.position(key) {
+            self.entries[i].val = value;
+            return self.touch(i);
+        }
+        if self.entries.len() as i32 == self.cap {
+            self.evict();
+        }
+        self.tick += 1;
+        self.entries.push(Entry {
+            key,
+            val: value,
+            freq: 1,
+            tick: self.tick,
+        });
+    }
+
+    fn resize(&mut self, capacity: i32) {
+        self.cap = capacity.max(0);
+        while self.entries.len() as i32 > self.cap {
+            self.evict();
+        }
+    }
+}
+
+impl Model for NaiveLfu {
+    fn create(capacity: i32) -> Self {
+        NaiveLfu {
+            cap: capacity.max(0),
+            tick: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    fn apply(&mut self, op: Op) -> i32 {
+        match op {
+            Op::Put(key, value) => self.put(key, value),
+            Op::Get(key) => return self.get(key),
+            Op::Clear => self.entries.clear(),
+            Op::Resize(capacity) => self.resize(capacity),
+        }
+        0
+    }
+
+    fn size(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+impl Model for SlabLFUCache {
+    fn create(capacity: i32) -> Self {
+        SlabLFUCache::new(capacity)
+    }
+
+    fn apply(&mut self, op: Op) -> i32 {
+        match op {
+            Op::Put(key, value) => self.put(key, value),
+            Op::Get(key) => return self.get(key),
+            Op::Clear => self.clear_cache(),
+            Op::Resize(_) => unreachable!("SlabLFUCache cannot resize"),
+        }
+        0
+    }
+
+    fn size(&self) -> usize {
+        Cache::len(self)
+    }
+}
+
+/*   ONE SHARD, SO EVICTION IS NOT SPLIT AND THE WHOLE CACHE IS ONE LFU   */
+impl Model for ShardedLfuCache {
+    fn create(capacity: i32) -> Self {
+        ShardedLfuCache::new(capacity, 1)
+    }
+
+    fn apply(&mut self, op: Op) -> i32 {
+        match op {
+            Op::Put(key, value) => ShardedLfuCache::put(self, key, value),
+            Op::Get(key) => return ShardedLfuCache::get(self, key),
+            Op::Clear => ShardedLfuCache::clear_cache(self),
+            Op::Resize(_) => unreachable!("ShardedLfuCache cannot resize"),
+        }
+        0
+    }
+
+    fn size(&self) -> usize {
+        ShardedLfuCache::len(self)
+    }
+}
+
+impl Model for SyncLfuCache {
+    fn create(capacity: i32) -> Self {
+        SyncLfuCache::new(capacity)
+    }
+
+    fn apply(&mut self, op: Op) -> i32 {
+        match op {
+            Op::Put(key, value) => SyncLfuCache::put(self, key, value),
+            Op::Get(key) => return SyncLfuCache::get(self, key),
+            Op::Clear => SyncLfuCache::clear_cache(self),
+            Op::Resize(_) => unreachable!("SyncLfuCache cannot resize"),
+        }
+        0
+    }
+
+    fn size(&self) -> usize {
+        SyncLfuCache::len(self)
+    }
+}
+
+/*   THE CAPACITY IS THE TYPE'S OWN, `check` IS ONLY EVER ASKED FOR N   */
+impl<const N: usize> Model for LfuArray<i32, i32, N> {
+    fn create(capacity: i32) -> Self {
+        assert_eq!(capacity as usize, N);
+        LfuArray::new()
+    }
+
+    fn apply(&mut self, op: Op) -> i32 {
+        match op {
+            Op::Put(key, value) => {
+                self.put(key, value);
+            }
+            Op::Get(key) => return self.get(&key).copied().unwrap_or(-1),
+            Op::Clear => self.clear(),
+            Op::Resize(_) => unreachable!("LfuArray cannot resize"),
+        }
+        0
+    }
+
+    fn size(&self) -> usize {
+        self.len()
+    }
+}
+
+/*
+    LAMBDA 0 WEIGHS EVERY ACCESS THE SAME, SO LRFU IS EXACTLY LFU. LAMBDA 1
+    HALVES AN ACCESS PER TICK, SO THE CRF STAYS BELOW 2 AND ANY NEWER ACCESS
+    OUTWEIGHS ALL OLDER ONES: LRFU IS EXACTLY LRU
+*/
+struct Lrfu<const LAMBDA: u8>(LRFUCache);
+
+impl<const LAMBDA: u8> Model for Lrfu<LAMBDA> {
+    fn create(capacity: i32) -> Self {
+        Lrfu(LRFUCache::new(capacity, LAMBDA as f64))
+    }
+
+    fn apply(&mut self, op: Op) -> i32 {
+        match op {
+            Op::Put(key, value) => self.0.put(key, value),
+            Op::Get(key) => return self.0.get(key),
+            Op::Clear => self.0.clear_cache(),
+            Op::Resize(_) => unreachable!("LRFUCache cannot resize"),
+        }
+        0
+    }
+
+    fn size(&self) -> usize {
+        Cache::len(&self.0)
+    }
+}
+
+impl Model for LRUCache {
+    fn create(capacity: i32) -> Self {
+        LRUCache::new(capacity)
+    }
+
+    fn apply(&mut self, op: Op) -> i32 {
+        match op {
+            Op::Put(key, value) => self.put(key, value),
+            Op::Get(key) => return self.get(key),
+            Op::Clear => self.clear_cache(),
+            Op::Resize(_) => unreachable!("LRUCache cannot resize"),
+        }
+        0
+    }
+
+    fn size(&self) -> usize {
+        Cache::len(self)
+    }
+}
+
+fn generate(seed: u64, capacity: i32, len: usize, resize: bool) -> Vec<Op> {
+    let mut rng = Rng::new(seed);
+    let keys = 2 * capacity as u64 + 2;
+    (0..len)
+        .map(|_| match rng.below(100) {
+            0 => Op::Clear,
+            1..=3 if resize => Op::Resize(rng.below(2 * capacity as u64 + 1)),
+            4..=49 => Op::Put(rng.below(keys), rng.below(1000)),
+            _ => Op::Get(rng.below(keys)),
+        })
+        .collect()
+}
+
+/*   INDEX OF THE FIRST OP AFTER WHICH THE TWO CACHES DISAGREE   */
+fn divergence<A: Model, B: Model>(capacity: i32, ops: &[Op]) -> Option<usize> {
+    let mut a = A::create(capacity);
+    let mut b = B::create(capacity);
+    ops.iter()
+        .position(|&op| a.apply(op) != b.apply(op) || a.size() != b.size())
+}
+
+fn shrink<A: Model, B: Model>(capacity: i32, ops: &[Op]) -> Vec<Op> {
+    let end = divergence::<A, B>(capacity, ops).unwrap();
+    let mut ops = ops[..=end].to_vec();
+    let mut chunk = ops.len() / 2;
+    while chunk > 0 {
+        let mut start = 0;
+        let mut removed = false;
+        while start < ops.len() {
+            let mut candidate = ops[..start].to_vec();
+            candidate.extend_from_slice(&ops[(start + chunk).min(ops.len())..]);
+            match divergence::<A, B>(capacity, &candidate) {
+                Some(end) => {
+                    candidate.truncate(end + 1);
+                    ops = candidate;
+                    removed = true;
+                }
+                None => start += chunk,
+            }
+        }
+        if !removed {
+            chunk /= 2;
+        }
+    }
+    ops
+}
+
+fn seeds() -> u64 {
+    std::env::var("LFU_DIFF_SEEDS")
+        .ok()
+        .and_then(|seeds| seeds.parse().ok())
+        .unwrap_or(100)
+}
+
+fn check<A: Model, B: Model>(
+    seeds: u64,
+    len: usize,
+    resize: bool,
+    capacities: RangeInclusive<i32>,
+) -> Result<(), String> {
+    for seed in 0..seeds {
+        for capacity in capacities.clone() {
+            let ops = generate(seed, capacity, len, resize);
+            if divergence::<A, B>(capacity, &ops).is_some() {
+                let shortest = shrink::<A, B>(capacity, &ops);
+                let mut report = format!(
+                    "diverged on seed {} with capacity {}, shortest sequence ({} ops):",
+                    seed,
+                    capacity,
+                    shortest.len()
+                );
+                for op in shortest {
+                    write!(report, "\n    {:?}", op).unwrap();
+                }
+                return Err(report);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn lfu_cache_matches_naive_lfu() {
+    if let Err(report) = check::<LFUCache, NaiveLfu>(seeds(), 2_000, true, 1..=6) {
+        panic!("{}", report);
+    }
+}
+
+#[test]
+fn slab_lfu_cache_matches_naive_lfu() {
+    if let Err(report) = check::<SlabLFUCache, NaiveLfu>(seeds(), 2_000, false, 1..=6) {
+        panic!("{}", report);
+    }
+}
+
+#[test]
+fn sync_lfu_cache_matches_naive_lfu() {
+    if let Err(report) = check::<SyncLfuCache, NaiveLfu>(seeds(), 2_000, false, 1..=6) {
+        panic!("{}", report);
+    }
+}
+
+#[test]
+fn single_shard_matches_naive_lfu() {
+    if let Err(report) = check::<ShardedLfuCache, NaiveLfu>(seeds(), 2_000, false, 1..=6) {
+        panic!("{}", report);
+    }
+}
+
+#[test]
+fn lfu_array_matches_naive_lfu() {
+    let report = check::<LfuArray<i32, i32, 1>, NaiveLfu>(seeds(), 2_000, false, 1..=1)
+        .and(check::<LfuArray<i32, i32, 2>, NaiveLfu>(
+            seeds(),
+            2_000,
+            false,
+            2..=2,
+        ))
+        .and(check::<LfuArray<i32, i32, 3>, NaiveLfu>(
+            seeds(),
+            2_000,
+            false,
+            3..=3,
+        ))
+        .and(check::<LfuArray<i32, i32, 6>, NaiveLfu>(
+            seeds(),
+            2_000,
+            false,
+            6..=6,
+        ))
+        .and(check::<LfuArray<i32, i32, 16>, NaiveLfu>(
+            seeds(),
+            2_000,
+            false,
+            16..=16,
+        ));
+    if let Err(report) = report {
+        panic!("{}", report);
+    }
+}
+
+#[test]
+fn lrfu_matches_lfu_and_lru_at_the_ends_of_lambda() {
+    let lfu = check::<Lrfu<0>, NaiveLfu>(seeds(), 2_000, false, 1..=6);
+    let lru = check::<Lrfu<1>, LRUCache>(seeds(), 2_000, false, 1..=6);
+    if let Err(report) = lfu.and(lru) {
+        panic!("{}", report);
+    }
+}
+
+/*   LRU IS ONLY CHECKED AGAINST LRFU, HERE IT STANDS IN FOR A BROKEN LFU   */
+#[test]
+fn harness_shrinks_a_divergence() {
+    let report = check::<LRUCache, NaiveLfu>(10, 500, false, 1..=6).unwrap_err();
+    let ops = report.lines().count() - 1;
+    assert!(ops <= 6, "{}", report);
+}
@@ -0,0 +1,643 @@
+/*
+    SEEDED BEHAVIOR CHECKS FOR THE POLICIES THAT HAVE NO REFERENCE MODEL.
+    EACH ONE RUNS RANDOM OPERATIONS AND CHECKS WHAT THE POLICY PROMISES
+    AFTER EVERY STEP, PLUS THE HAND-WORKED CASES THAT ONCE WENT WRONG
+*/
+mod common;
+
+use common::Rng;
+use lfu::cache::Cache;
+use lfu::cache::ARC::ARCCache;
+use lfu::cache::CLOCK::CLOCKCache;
+use lfu::cache::FIFO::FIFOCache;
+use lfu::cache::GDSF::GDSFCache;
+use lfu::cache::LFU::{Counter, LFUCache, Lookup, Window};
+use lfu::cache::NAMESPACE::NamespacedLFUCache;
+use lfu::cache::SAMPLED::SampledLFUCache;
+use lfu::cache::SHARDED::ShardedLfuCache;
+use lfu::cache::SLFU::SLFUCache;
+use lfu::cache::SYNC::SyncLfuCache;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SEEDS: u64 = 50;
+
+thread_local! {
+    static SKEW: Cell<Duration> = const { Cell::new(Duration::from_secs(0)) };
+}
+
+/*   THE REAL CLOCK PLUS WHATEVER THIS THREAD HAS SKIPPED AHEAD WITH `advance`   */
+fn skewed_clock() -> Instant {
+    Instant::now() + SKEW.with(Cell::get)
+}
+
+fn advance(by: Duration) {
+    SKEW.with(|skew| skew.set(skew.get() + by));
+}
+
+/*   WHETHER `Display` LISTS THE ENTRY FIRST IN THE BUCKET OF `freq`   */
+fn heads_bucket(cache: &LFUCache, freq: i32, key: i32, value: i32) -> bool {
+    let entry = format!("frequency {} : [ key: {}, value: {} ]", freq, key, value);
+    cache.to_string().contains(&entry)
+}
+
+#[test]
+fn arc_ghost_hit_after_remove_does_not_evict() {
+    let mut cache = ARCCache::new(2);
+    cache.put(1, 1);
+    cache.get(1);
+    cache.put(2, 2);
+    cache.put(3, 3);
+    cache.remove(3);
+    cache.put(2, 20);
+    assert_eq!(Cache::len(&cache), 2);
+    assert_eq!(cache.get(1), 1);
+    assert_eq!(cache.get(2), 20);
+}
+
+/*   THE LATEST VALUE IS RETURNED, NOTHING IS EVICTED BEFORE THE CACHE IS FULL AND IT NEVER GOES OVER   */
+fn keeps_latest_values<C: Cache<i32, i32>>(new: impl Fn(i32) -> C) {
+    for seed in 0..SEEDS {
+        let mut rng = Rng::new(seed);
+        let cap = 1 + rng.below(8);
+        let mut cache = new(cap);
+        let mut written = HashMap::new();
+        for _ in 0..2000 {
+            let key = rng.below(3 * cap as u64);
+            match rng.below(10) {
+                0..=4 => {
+                    let value = rng.below(1000);
+                    let len = cache.len();
+                    let resident = cache.get(&key).is_some();
+                    cache.put(key, value);
+                    written.insert(key, value);
+                    let expected = if resident || len == cap as usize {
+                        len
+                    } else {
+                        len + 1
+                    };
+                    assert_eq!(cache.len(), expected, "seed {}", seed);
+                    assert_eq!(cache.get(&key), Some(value), "seed {}", seed);
+                }
+                5 => {
+                    if let Some(value) = cache.remove(&key) {
+                        assert_eq!(Some(&value), written.get(&key), "seed {}", seed);
+                    }
+                }
+                _ => {
+                    if let Some(value) = cache.get(&key) {
+                        assert_eq!(Some(&value), written.get(&key), "seed {}", seed);
+                    }
+                }
+            }
+            assert!(cache.len() <= cap as usize, "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn arc_keeps_latest_values_and_only_evicts_when_full() {
+    keeps_latest_values(ARCCache::new);
+}
+
+#[test]
+fn fifo_keeps_latest_values_and_only_evicts_when_full() {
+    keeps_latest_values(FIFOCache::new);
+}
+
+#[test]
+fn fifo_evicts_in_insertion_order_whatever_the_hits() {
+    let mut cache = FIFOCache::new(3);
+    for key in 1..=3 {
+        cache.put(key, key);
+    }
+    for _ in 0..10 {
+        cache.get(1);
+    }
+    cache.put(3, 30);
+    cache.put(4, 4);
+    assert_eq!(cache.get(1), -1);
+    cache.put(5, 5);
+    assert_eq!(cache.get(2), -1);
+    cache.put(6, 6);
+    assert_eq!(cache.get(3), -1);
+    for key in 4..=6 {
+        assert_eq!(cache.get(key), key);
+    }
+}
+
+#[test]
+fn clock_keeps_latest_values_and_only_evicts_when_full() {
+    keeps_latest_values(CLOCKCache::new);
+}
+
+#[test]
+fn clock_gives_referenced_keys_a_second_chance() {
+    let mut cache = CLOCKCache::new(3);
+    for key in 1..=3 {
+        cache.put(key, key);
+    }
+    cache.get(1);
+
+    /*   THE HAND CLEARS 1'S BIT AND TAKES 2, THEN 3, THEN 1 ONCE IT IS NO LONGER REFERENCED   */
+    cache.put(4, 4);
+    assert_eq!(Cache::get(&mut cache, &2), None);
+    cache.put(5, 5);
+    assert_eq!(Cache::get(&mut cache, &3), None);
+    cache.put(6, 6);
+    assert_eq!(Cache::get(&mut cache, &1), None);
+    for key in 4..=6 {
+        assert_eq!(cache.get(key), key);
+    }
+}
+
+#[test]
+fn sampled_keeps_latest_values_and_only_evicts_when_full() {
+    keeps_latest_values(SampledLFUCache::new);
+}
+
+/*   WITH ENOUGH SAMPLES A HOT KEY IS ONLY PICKED IF EVERY SAMPLE LANDS ON A HOT KEY   */
+#[test]
+fn sampled_eviction_spares_hot_keys() {
+    for seed in 0..SEEDS {
+        let mut rng = Rng::new(seed);
+        let cap = 4 + rng.below(12);
+        let hot = 1 + rng.below(cap as u64 / 2);
+        let mut cache = SampledLFUCache::with_samples(cap, 64);
+        for key in 0..hot {
+            cache.put(key, key);
+            for _ in 0..5 {
+                cache.get(key);
+            }
+        }
+        for key in hot..hot + 1000 {
+            cache.put(key, key);
+        }
+        for key in 0..hot {
+            assert_eq!(cache.get(key), key, "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn slfu_keeps_latest_values_and_only_evicts_when_full() {
+    keeps_latest_values(|cap| SLFUCache::new(cap, cap / 2, 3));
+}
+
+#[test]
+fn slfu_protected_keys_survive_a_flood_of_new_ones() {
+    for seed in 0..SEEDS {
+        let mut rng = Rng::new(seed);
+        let cap = 2 + rng.below(7);
+        let protected = 1 + rng.below(cap as u64 - 1);
+        let mut cache = SLFUCache::new(cap, protected, 3);
+        for key in 0..protected {
+            cache.put(key, key);
+            cache.get(key);
+            cache.get(key);
+        }
+        /*   EVERY NEWCOMER IS READ AT MOST ONCE, SO NONE OF THEM REACHES THE THRESHOLD   */
+        for key in protected..protected + 500 {
+            cache.put(key, key);
+            if rng.below(2) == 0 {
+                cache.get(key);
+            }
+        }
+        for key in 0..protected {
+            assert_eq!(cache.get(key), key, "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn slfu_promotes_at_the_threshold_and_demotes_when_protected_is_full() {
+    let mut cache = SLFUCache::new(3, 2, 2);
+    cache.put(1, 1);
+    cache.get(1);
+    cache.put(2, 2);
+    cache.get(2);
+
+    /*   1 AND 2 ARE PROTECTED, SO A FULL CACHE EVICTS THE NEWCOMER 3 AND NOT THEM   */
+    cache.put(3, 3);
+    cache.put(4, 4);
+    assert_eq!(cache.get(3), -1);
+    assert_eq!(cache.get(1), 1);
+    assert_eq!(cache.get(2), 2);
+
+    /*   PROMOTING 4 DEMOTES 1, THE OLDER OF THE TWO, AND PROBATION IS WHERE VICTIMS COME FROM   */
+    cache.get(4);
+    cache.put(5, 5);
+    assert_eq!(cache.get(1), -1);
+    assert_eq!(cache.get(2), 2);
+    assert_eq!(cache.get(4), 4);
+}
+
+/*   THE MINS ALWAYS FIT THE CAPACITY, SO EVERY GUARANTEE HOLDS   */
+#[test]
+fn namespaces_stay_within_their_quotas() {
+    for seed in 0..SEEDS {
+        let mut rng = Rng::new(seed);
+        let cap = 4 + rng.below(12);
+        let mut cache = NamespacedLFUCache::new(cap);
+        let mut quotas = Vec::new();
+        let mut spare = cap;
+        for ns in 0..3 {
+            let min = rng.below(spare as u64 / 2 + 1);
+            let max = min + rng.below((cap - min) as u64 + 1);
+            spare -= min;
+            cache.set_quota(ns, min, max);
+            quotas.push((min, max));
+        }
+        for _ in 0..2000 {
+            let ns = rng.below(4);
+            let key = rng.below(2 * cap as u64);
+            let before: Vec<i32> = (0..4).map(|ns| cache.len_of(ns)).collect();
+            match rng.below(10) {
+                0..=5 => cache.put(ns, key, key),
+                6 => {
+                    cache.remove(ns, key);
+                }
+                _ => {
+                    let value = cache.get(ns, key);
+                    assert!(value == -1 || value == key, "seed {}", seed);
+                }
+            }
+            for (other, &(min, max)) in quotas.iter().enumerate() {
+                let len = cache.len_of(other as i32);
+                assert!(len <= max, "seed {}", seed);
+                if other as i32 != ns {
+                    assert!(len >= before[other].min(min), "seed {}", seed);
+                }
+            }
+            let total: i32 = (0..4).map(|ns| cache.len_of(ns)).sum();
+            assert_eq!(total as usize, Cache::len(&cache), "seed {}", seed);
+            assert!(total <= cap, "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn noisy_namespace_only_pushes_others_down_to_their_min() {
+    let mut cache = NamespacedLFUCache::new(4);
+    cache.set_quota(1, 2, 4);
+    cache.put(1, 1, 1);
+    cache.put(1, 2, 2);
+    cache.put(1, 3, 3);
+    for key in 10..20 {
+        cache.put(2, key, key);
+    }
+    assert_eq!(cache.len_of(1), 2);
+    assert_eq!(cache.len_of(2), 2);
+
+    /*   AT ITS MAX A NAMESPACE EVICTS ITS OWN KEYS, EVEN WITH ROOM ELSEWHERE   */
+    cache.set_quota(2, 0, 1);
+    assert_eq!(cache.len_of(2), 1);
+    cache.put(2, 20, 20);
+    assert_eq!(cache.len_of(2), 1);
+    assert_eq!(cache.get(2, 20), 20);
+}
+
+#[test]
+fn oversubscribed_mins_keep_the_cache_within_its_capacity() {
+    let mut cache = NamespacedLFUCache::new(4);
+    cache.set_quota(1, 3, 4);
+    cache.set_quota(2, 3, 4);
+    for key in 0..3 {
+        cache.put(1, key, key);
+    }
+    for key in 10..15 {
+        cache.put(2, key, key);
+    }
+
+    /*   NOBODY IS OVER THEIR MIN, SO 2 ONLY REPLACES ITS OWN KEY AND A NEWCOMER GETS NO ROOM   */
+    assert_eq!(cache.len_of(1), 3);
+    assert_eq!(cache.len_of(2), 1);
+    assert_eq!(cache.get(2, 14), 14);
+    cache.put(3, 20, 20);
+    assert_eq!(cache.get(3, 20), -1);
+    assert_eq!(Cache::len(&cache), 4);
+
+    /*   EVERY MIN IS AT LEAST HALF THE CAPACITY, SO THE FOUR OF THEM NEVER FIT   */
+    for seed in 0..SEEDS {
+        let mut rng = Rng::new(seed);
+        let cap = 2 + rng.below(8);
+        let mut cache = NamespacedLFUCache::new(cap);
+        for ns in 0..4 {
+            let min = cap / 2 + rng.below(cap as u64);
+            cache.set_quota(ns, min, min + rng.below(4));
+        }
+        for _ in 0..2000 {
+            let (ns, key) = (rng.below(4), rng.below(3 * cap as u64));
+            match rng.below(4) {
+                0..=2 => cache.put(ns, key, key),
+                _ => {
+                    cache.remove(ns, key);
+                }
+            }
+            let total: i32 = (0..4).map(|ns| cache.len_of(ns)).sum();
+            assert_eq!(total as usize, Cache::len(&cache), "seed {}", seed);
+            assert!(total <= cap, "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn accesses_older_than_the_window_stop_counting() {
+    let mut cache = LFUCache::new(2);
+    cache.set_window(Window::Operations(10), 5);
+    cache.put(1, 1);
+    for _ in 0..5 {
+        cache.get(1);
+    }
+    for _ in 0..20 {
+        cache.get(99);
+    }
+
+    /*   1 WAS READ 5 TIMES, BUT ALL OF THEM FELL OUT OF THE WINDOW, WHILE 2 HAS 3 RECENT ACCESSES   */
+    cache.put(2, 2);
+    cache.get(2);
+    cache.get(2);
+    cache.put(3, 3);
+    assert_eq!(cache.get(1), -1);
+    assert_eq!(cache.get(2), 2);
+}
+
+#[test]
+fn operations_window_narrower_than_its_slots_keeps_its_length() {
+    let mut cache = LFUCache::new(2);
+    cache.set_window(Window::Operations(3), 8);
+    cache.put(1, 1);
+    cache.get(1);
+    cache.get(1);
+    cache.get(99);
+    cache.get(99);
+
+    /*   ONLY THE LAST 3 OPERATIONS COUNT, SO 1 IS BACK TO 1 AND 2 HAS 2   */
+    cache.put(2, 2);
+    cache.get(2);
+    cache.put(3, 3);
+    assert_eq!(cache.get(1), -1);
+    assert_eq!(cache.get(2), 2);
+}
+
+#[test]
+fn accesses_older_than_a_time_window_stop_counting() {
+    let window = Duration::from_secs(60);
+    let mut cache = LFUCache::new(2);
+    cache.set_clock(skewed_clock);
+    cache.set_window(Window::Time(window), 4);
+    cache.put(1, 1);
+    for _ in 0..5 {
+        cache.get(1);
+    }
+    assert!(heads_bucket(&cache, 6, 1, 1), "{}", cache);
+    advance(window / 2);
+    assert_eq!(cache.check_invariants(), Ok(()));
+
+    /*   HALF A WINDOW LATER THE READS STILL COUNT, A FULL ONE AFTER THEM THEY ALL AGED OUT   */
+    cache.get(99);
+    assert!(heads_bucket(&cache, 6, 1, 1), "{}", cache);
+    advance(window);
+    cache.put(2, 2);
+    cache.get(2);
+    cache.get(2);
+    assert!(heads_bucket(&cache, 1, 1, 1), "{}", cache);
+    assert_eq!(cache.check_invariants(), Ok(()));
+
+    cache.put(3, 3);
+    assert_eq!(cache.get(1), -1);
+    assert_eq!(cache.get(2), 2);
+    assert_eq!(cache.check_invariants(), Ok(()));
+}
+
+/*   EVERY GET IS A HIT OR A MISS AND EVERY NEW KEY THAT DID NOT GROW THE CACHE EVICTED ONE   */
+#[test]
+fn sharded_stats_account_for_every_operation() {
+    for seed in 0..SEEDS {
+        let mut rng = Rng::new(seed);
+        let cap = 1 + rng.below(32);
+        let shards = 1 + rng.below(8) as usize;
+        let cache = ShardedLfuCache::new(cap, shards);
+        let stats = cache.stats();
+        assert_eq!(cache.capacity(), cap as usize, "seed {}", seed);
+        assert_eq!(stats.len(), shards.min(cap as usize), "seed {}", seed);
+        let shares: Vec<usize> = stats.iter().map(|shard| shard.capacity).collect();
+        assert!(
+            shares.iter().max().unwrap() - shares.iter().min().unwrap() <= 1,
+            "seed {}",
+            seed
+        );
+
+        let (mut gets, mut evictions) = (0, 0);
+        for _ in 0..2000 {
+            let key = rng.below(3 * cap as u64);
+            if rng.below(2) == 0 {
+                let value = cache.get(key);
+                assert!(value == -1 || value == key, "seed {}", seed);
+                gets += 1;
+            } else {
+                let len = cache.len();
+                let resident = cache.iter().any(|(k, _)| k == key);
+                cache.put(key, key);
+                if !resident && cache.len() == len {
+                    evictions += 1;
+                }
+            }
+            for shard in cache.stats() {
+                assert!(shard.len <= shard.capacity, "seed {}", seed);
+            }
+        }
+        let stats = cache.stats();
+        let counted: u64 = stats.iter().map(|shard| shard.hits + shard.misses).sum();
+        assert_eq!(counted, gets, "seed {}", seed);
+        let evicted: u64 = stats.iter().map(|shard| shard.evictions).sum();
+        assert_eq!(evicted, evictions, "seed {}", seed);
+        assert_eq!(cache.len(), cache.iter().count(), "seed {}", seed);
+    }
+}
+
+/*   EVERY THREAD WRITES key * 10 + ITS OWN INDEX, SO ANY VALUE READ BACK MUST DECODE TO ITS KEY   */
+#[test]
+fn sync_cache_shared_across_threads_stays_consistent() {
+    let cache = Arc::new(SyncLfuCache::new(16));
+    let threads: Vec<_> = (0..4)
+        .map(|t| {
+            let cache = Arc::clone(&cache);
+            thread::spawn(move || {
+                let mut rng = Rng::new(t);
+                for _ in 0..20_000 {
+                    let key = rng.below(64);
+                    match rng.below(10) {
+                        0..=3 => cache.put(key, key * 10 + t as i32),
+                        4 => {
+                            if let Some(value) = cache.remove(key) {
+                                assert_eq!(value / 10, key);
+                            }
+                        }
+                        _ => {
+                            let value = cache.get(key);
+                            assert!(value == -1 || value / 10 == key);
+                        }
+                    }
+                    assert!(cache.len() <= 16);
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    let resident = (0..64).filter(|&key| cache.get(key) != -1).count();
+    assert_eq!(resident, cache.len());
+}
+
+/*   `Display` WRITES THE ENTRIES WHILE HOLDING THE LOCK, SO A SINK THAT PANICS ON ONE POISONS IT   */
+struct Exploding;
+
+impl fmt::Write for Exploding {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        assert!(!s.contains("value"), "display interrupted");
+        Ok(())
+    }
+}
+
+#[test]
+fn shared_caches_pass_on_a_poisoned_lock() {
+    let sync = SyncLfuCache::new(2);
+    sync.put(1, 1);
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| write!(Exploding, "{}", sync))).is_err());
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| sync.get(1))).is_err());
+
+    let sharded = ShardedLfuCache::new(2, 1);
+    sharded.put(1, 1);
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| write!(Exploding, "{}", sharded))).is_err());
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| sharded.get(1))).is_err());
+}
+
+#[test]
+fn lfu_large_increments_jump_to_their_bucket() {
+    let mut cache = LFUCache::new(3);
+    cache.set_increments(200_000, 1);
+    cache.put(1, 1);
+    cache.put(2, 2);
+    cache.put(3, 3);
+    assert_eq!(cache.get(1), 1);
+    for value in 20..25 {
+        cache.put(2, value);
+    }
+    assert_eq!(cache.get(3), 3);
+    assert_eq!(cache.get(3), 3);
+
+    /*   1 HAS 200_001, 3 HAS 400_001 AND 2 HAS 6, SO 2 GOES FIRST, THEN THE NEWCOMER   */
+    cache.put(4, 4);
+    assert_eq!(cache.get(2), -1);
+    cache.put(5, 5);
+    assert_eq!(cache.get(4), -1);
+    assert_eq!(cache.get(1), 1);
+    assert_eq!(cache.get(3), 3);
+}
+
+#[test]
+fn log_counter_decays_keys_that_are_never_read_again() {
+    let decay = Duration::from_secs(60);
+    let mut cache = LFUCache::with_counter(3, Counter::Logarithmic { factor: 0, decay });
+    cache.set_clock(skewed_clock);
+    cache.put(1, 1);
+    for _ in 0..50 {
+        cache.get(1);
+    }
+    advance(decay * 100);
+
+    /*   1 IS NOW FAR PAST ITS DECAY, 2 AND 3 ARE FRESH BUT LESS READ THAN 1 EVER WAS   */
+    cache.put(2, 2);
+    cache.put(3, 3);
+    for _ in 0..10 {
+        cache.get(2);
+        cache.get(3);
+    }
+    cache.put(4, 4);
+    assert_eq!(cache.get(1), -1);
+    assert_eq!(cache.get(2), 2);
+    assert_eq!(cache.get(3), 3);
+}
+
+#[test]
+fn expired_absent_markers_make_room_before_live_ones() {
+    let ttl = Duration::from_secs(60);
+    let mut cache = LFUCache::new(4);
+    cache.set_clock(skewed_clock);
+    cache.set_negative_caching(2, ttl);
+    cache.put_absent(1);
+    for _ in 0..3 {
+        assert_eq!(cache.lookup(1), Lookup::Absent);
+    }
+    advance(ttl + ttl / 4);
+
+    /*   1 IS THE MOST USED MARKER BUT EXPIRED, SO 3 TAKES ITS PLACE AND NOT 2'S   */
+    cache.put_absent(2);
+    cache.put_absent(3);
+    assert_eq!(cache.lookup(2), Lookup::Absent);
+    assert_eq!(cache.lookup(3), Lookup::Absent);
+    assert_eq!(cache.lookup(1), Lookup::Miss);
+}
+
+#[test]
+fn gdsf_rejected_update_keeps_the_cached_entry() {
+    let mut cache = GDSFCache::new(10);
+    cache.put_with(1, 1, 1.0, 4);
+    cache.put_with(1, 2, 1.0, 11);
+    cache.put_with(1, 3, 1.0, 0);
+    assert_eq!(cache.get(1), 1);
+    assert_eq!(Cache::len(&cache), 1);
+}
+
+#[test]
+fn lfu_frequencies_stop_at_i32_max() {
+    for window in [None, Some(Window::Operations(100))].iter() {
+        let mut cache = LFUCache::new(2);
+        cache.set_increments(i32::MAX / 2 + 1, 1);
+        if let Some(window) = *window {
+            cache.set_window(window, 4);
+        }
+        cache.put(1, 1);
+        cache.put(2, 2);
+        for _ in 0..4 {
+            assert_eq!(cache.get(1), 1);
+            assert_eq!(cache.check_invariants(), Ok(()), "{:?}", window);
+        }
+        assert!(heads_bucket(&cache, i32::MAX, 1, 1), "{}", cache);
+
+        /*   1 DID NOT WRAP AROUND TO THE FRONT OF THE CHAIN, SO 2 IS STILL THE VICTIM   */
+        cache.put(3, 3);
+        assert_eq!(cache.get(2), -1, "{:?}", window);
+        assert_eq!(cache.get(1), 1);
+        assert_eq!(cache.check_invariants(), Ok(()), "{:?}", window);
+    }
+}
+
+#[test]
+fn lfu_zero_write_increment_updates_in_place() {
+    let mut cache = LFUCache::new(2);
+    cache.set_increments(1, 0);
+    cache.put(1, 1);
+    cache.put(2, 2);
+    for value in 10..60 {
+        cache.put(1, value);
+    }
+    assert_eq!(cache.check_invariants(), Ok(()));
+    assert!(heads_bucket(&cache, 1, 1, 59), "{}", cache);
+
+    /*   50 WRITES LEFT 1 AT FREQUENCY 1, SO ONE READ MORE OF 2 THAN OF 1 MAKES 1 THE VICTIM   */
+    cache.get(2);
+    cache.get(2);
+    assert_eq!(cache.get(1), 59);
+    cache.put(3, 3);
+    assert_eq!(cache.get(1), -1);
+    assert_eq!(cache.get(2), 2);
+    assert_eq!(cache.check_invariants(), Ok(()));
+}